@@ -1,5 +1,79 @@
 use chrono::prelude::*;
-use std::process::Command;
+use std::{collections::BTreeMap, fs, path::Path, process::Command};
+
+// file types the game has asset loaders for, anything else would never finish loading
+const EXTENSIONS:[&str; 5] = ["json", "ogg", "png", "tmx", "ttf"];
+
+// collects the paths of all files below dir relative to the assets folder
+fn glob(root:&Path, dir:&Path, files:&mut Vec<String>) {
+    let entries = fs::read_dir(dir).unwrap_or_else(|err| panic!("could not read asset folder {}: {}", dir.display(), err));
+    let mut entries:Vec<_> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            glob(root, &path, files);
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            if loadable(&relative) {
+                files.push(relative);
+            }
+        }
+    }
+}
+
+// resolves a path such as ../imgs/tiles.png relative to the folder of the file referencing it
+fn resolve(file:&str, source:&str) -> String {
+    let mut parts:Vec<&str> = file.split('/').collect();
+    parts.pop();
+    for part in source.split('/') {
+        match part {
+            ".." => { parts.pop(); }
+            "." | "" => {}
+            part => parts.push(part)
+        }
+    }
+    parts.join("/")
+}
+
+fn loadable(file:&str) -> bool {
+    let extension = Path::new(file).extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    EXTENSIONS.contains(&extension.as_str())
+}
+
+// files referenced by a map, including those referenced by external tilesets
+fn references(root:&Path, file:&str, group:&mut Vec<String>, visited:&mut Vec<String>) {
+    if visited.iter().any(|f| f == file) {
+        return;
+    }
+    visited.push(file.into());
+    if loadable(file) {
+        group.push(file.into());
+    }
+
+    let content = fs::read_to_string(root.join(file)).unwrap_or_default();
+    for source in content.split("source=\"").skip(1) {
+        if let Some(end) = source.find('"') {
+            references(root, &resolve(file, &source[..end]), group, visited);
+        }
+    }
+}
+
+fn manifest() -> String {
+    let root = Path::new("assets");
+    let mut files = Vec::new();
+    glob(root, root, &mut files);
+
+    // every map is a group of its own, everything else is preloaded at startup
+    let mut groups = BTreeMap::new();
+    groups.insert("common".to_string(), files.iter().filter(|f| f.starts_with("maps/") == false).cloned().collect::<Vec<_>>());
+    for map in files.iter().filter(|f| f.starts_with("maps/")) {
+        let mut group = Vec::new();
+        references(root, map, &mut group, &mut Vec::new());
+        groups.insert(map.clone(), group);
+    }
+
+    serde_json::to_string_pretty(&serde_json::json!({ "groups": groups })).expect("could not serialize the asset manifest")
+}
 
 fn main() {
     let output = Command::new("git").args(&["rev-parse", "--short", "HEAD"]).output().unwrap();
@@ -8,4 +82,14 @@ fn main() {
     let build_date = Utc::now();
     println!("cargo:rustc-env=GIT_HASH={}", git_hash);
     println!("cargo:rustc-env=BUILD_DATE={}", build_date.date());
-}
\ No newline at end of file
+
+    // the manifest is embedded in both native and web builds, it has to be regenerated whenever an asset is added or removed
+    println!("cargo:rerun-if-changed=assets");
+    println!("cargo:rerun-if-changed=build.rs");
+    // without these the git hash would only be updated along with the assets
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    let path = Path::new(&out_dir).join("manifest.json");
+    fs::write(&path, manifest()).unwrap_or_else(|err| panic!("could not write {}: {}", path.display(), err));
+}
@@ -0,0 +1,46 @@
+use std::collections::HashMap;
+
+use serde::{Serialize, Deserialize};
+
+use super::AudioBus;
+
+// a named sound, played as one of its variations picked at random
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundDef {
+    pub variations:Vec<String>,
+    // random volume and pitch are picked between min and max
+    pub volume:(f32, f32),
+    pub pitch:(f32, f32),
+    // sounds with a higher priority take channels from sounds with a lower priority
+    pub priority:i32,
+    pub bus:Option<AudioBus>
+}
+
+impl Default for SoundDef {
+    fn default() -> Self {
+        Self {
+            variations:Vec::new(),
+            volume:(1.0, 1.0),
+            pitch:(1.0, 1.0),
+            priority:0,
+            bus:None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SoundBank {
+    pub sounds:HashMap<String, SoundDef>
+}
+
+impl SoundBank {
+    pub fn get(&self, name:&str) -> Option<&SoundDef> {
+        self.sounds.get(name)
+    }
+
+    // paths of all variations of all sounds
+    pub fn paths(&self) -> Vec<String> {
+        self.sounds.values().flat_map(|s| s.variations.iter().cloned()).collect()
+    }
+}
@@ -1,23 +1,63 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 use bevy_kira_audio::AudioChannel;
+use rand::{Rng, random, thread_rng};
+use serde::{Serialize, Deserialize};
+
+mod music;
+pub use music::*;
+use music::music_channel;
+
+mod bank;
+pub use bank::*;
 
 pub struct AudioPlugin;
 
+// number of channels positional sound effects are spread over, each playing one sound with its own volume and panning
+const SFX_CHANNELS:usize = 8;
+// seconds after which a sound no longer protects its channel from sounds with a lower priority
+const CHANNEL_HOLD:f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioBus {
+    Music,
+    Sfx,
+    Ui
+}
+
 pub struct PlayAudioEvent {
+    // name of a sound in the sound bank or the path of a sound file
     pub path:String,
-    pub music:bool
+    pub music:bool,
+    pub bus:AudioBus,
+    // world position of the sound, played without panning and attenuation if none
+    pub position:Option<Vec3>
 }
 
 impl PlayAudioEvent {
     pub fn new(path:&str) -> Self {
         Self {
             path:path.into(),
-            music:false
+            music:false,
+            bus:AudioBus::Sfx,
+            position:None
         }
     }
 
     pub fn with_music(mut self, music:bool) -> Self {
         self.music = music;
+        self.bus = if music { AudioBus::Music } else { AudioBus::Sfx };
+        self
+    }
+
+    pub fn with_bus(mut self, bus:AudioBus) -> Self {
+        self.bus = bus;
+        self.music = bus == AudioBus::Music;
+        self
+    }
+
+    pub fn with_position(mut self, position:Vec3) -> Self {
+        self.position = Some(position);
         self
     }
 }
@@ -30,39 +70,195 @@ impl From<&str> for PlayAudioEvent {
 
 impl From<String> for PlayAudioEvent {
     fn from(str: String) -> Self {
+        Self::new(&str)
+    }
+}
+
+// volumes of the channels, all between 0.0 and 1.0
+#[derive(Debug, Clone, Copy)]
+pub struct AudioMixer {
+    pub master:f32,
+    pub music:f32,
+    pub sfx:f32,
+    pub ui:f32,
+    // suspends every channel, such as while the game is paused
+    pub paused:bool,
+    // seconds before the same sound can be played again
+    pub cooldown:f32,
+    // distance from the listener at which positional sounds can no longer be heard
+    pub max_distance:f32,
+    // horizontal distance from the listener at which sounds are panned fully to one side
+    pub pan_distance:f32
+}
+
+impl Default for AudioMixer {
+    fn default() -> Self {
         Self {
-            path:str,
-            music:false
+            master:1.0,
+            music:0.5,
+            sfx:1.0,
+            ui:1.0,
+            paused:false,
+            cooldown:0.05,
+            max_distance:24.0,
+            pan_distance:12.0
+        }
+    }
+}
+
+impl AudioMixer {
+    pub fn volume(&self, bus:AudioBus) -> f32 {
+        let v = match bus {
+            AudioBus::Music => self.music,
+            AudioBus::Sfx => self.sfx,
+            AudioBus::Ui => self.ui
+        };
+        (self.master * v).max(0.0).min(1.0)
+    }
+
+    // volume and panning of a sound at position p heard from the listener
+    pub fn spatialize(&self, p:Vec3, listener:Vec3) -> (f32, f32) {
+        let d = p - listener;
+        let attenuation = (1.0 - d.truncate().length() / self.max_distance).max(0.0);
+        let pan = 0.5 + (d.x / self.pan_distance).max(-1.0).min(1.0) * 0.5;
+        (attenuation * attenuation, pan)
+    }
+}
+
+// entity whose position positional sounds are heard from, usually the camera
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AudioListener;
+
+#[derive(Default)]
+struct AudioState {
+    // time each sound was last played
+    last_played:HashMap<String, f64>,
+    // priority and start time of the sound last played in each sfx channel
+    sfx_channels:[(i32, f64); SFX_CHANNELS]
+}
+
+impl AudioState {
+    // picks the channel to play a sound in, stealing from the least important sound
+    fn sfx_channel(&mut self, priority:i32, now:f64) -> Option<usize> {
+        let (index, (p, started)) = self.sfx_channels.iter().copied().enumerate()
+            .min_by(|(_, a), (_, b)| a.0.cmp(&b.0).then(a.1.partial_cmp(&b.1).unwrap()))?;
+        if p > priority && now - started < CHANNEL_HOLD {
+            return None;
         }
+
+        self.sfx_channels[index] = (priority, now);
+        Some(index)
+    }
+}
+
+// one-shot sounds on the music bus, such as stingers, get a channel of their own,
+// separate from the channels the music director crossfades tracks on
+fn channel(bus:AudioBus, index:usize) -> AudioChannel {
+    match bus {
+        AudioBus::Music => AudioChannel::new("music".into()),
+        AudioBus::Ui => AudioChannel::new("ui".into()),
+        AudioBus::Sfx => AudioChannel::new(format!("sfx_{}", index))
     }
 }
 
-fn player(asset_server:Res<AssetServer>, mut reader:EventReader<PlayAudioEvent>, audio:Res<bevy_kira_audio::Audio>) {
+fn player(asset_server:Res<AssetServer>,
+    mut reader:EventReader<PlayAudioEvent>,
+    mut music:EventWriter<MusicEvent>,
+    audio:Res<bevy_kira_audio::Audio>,
+    mixer:Res<AudioMixer>,
+    bank:Res<SoundBank>,
+    mut state:Local<AudioState>,
+    time:Res<Time>,
+    listener:Query<&GlobalTransform, With<AudioListener>>) {
+    let now = time.seconds_since_startup();
+    let listener = listener.iter().next().map(|t| t.translation);
     for e in reader.iter() {
         if e.music == false {
-            if e.path.len() > 0 {
-                let res = asset_server.load(e.path.as_str());
-                audio.play(res);
+            if e.path.len() > 0 && mixer.paused == false {
+                // skip identical sounds played right after each other
+                if let Some(last) = state.last_played.get(&e.path) {
+                    if now - *last < mixer.cooldown as f64 {
+                        continue;
+                    }
+                }
+                state.last_played.insert(e.path.clone(), now);
+
+                // sounds from the bank pick a variation, anything else is played as a file
+                let (path, volume, pitch, priority, bus) = match bank.get(&e.path) {
+                    Some(sound) if sound.variations.len() > 0 => {
+                        let mut rng = thread_rng();
+                        let path = sound.variations[rng.gen_range(0..sound.variations.len())].clone();
+                        let volume = sound.volume.0 + (sound.volume.1 - sound.volume.0) * random::<f32>();
+                        let pitch = sound.pitch.0 + (sound.pitch.1 - sound.pitch.0) * random::<f32>();
+                        (path, volume, pitch, sound.priority, sound.bus.unwrap_or(e.bus))
+                    }
+                    _ => (e.path.clone(), 1.0, 1.0, 0, e.bus)
+                };
+
+                let res = asset_server.load(path.as_str());
+                if bus == AudioBus::Sfx {
+                    let (attenuation, pan) = match (e.position, listener) {
+                        (Some(p), Some(listener)) => mixer.spatialize(p, listener),
+                        _ => (1.0, 0.5)
+                    };
+                    if attenuation <= 0.0 {
+                        continue;
+                    }
+
+                    if let Some(index) = state.sfx_channel(priority, now) {
+                        // volume, panning and pitch are set per channel, so the sound stolen from must not keep playing with them
+                        let channel = channel(AudioBus::Sfx, index);
+                        audio.stop_channel(&channel);
+                        audio.set_volume_in_channel(attenuation * volume * mixer.volume(AudioBus::Sfx), &channel);
+                        audio.set_panning_in_channel(pan, &channel);
+                        audio.set_playback_rate_in_channel(pitch, &channel);
+                        audio.play_in_channel(res, &channel);
+                    }
+                } else {
+                    audio.play_in_channel(res, &channel(bus, 0));
+                }
             }
+        } else if e.path.len() > 0 {
+            music.send(MusicEvent::Play(Playlist::single(&e.path)));
         } else {
-            audio.stop_channel(&AudioChannel::new("music".into()));
-            if e.path.len() > 0 {
-                let res = asset_server.load(e.path.as_str());
-                audio.play_looped_in_channel(res, &AudioChannel::new("music".into()));
-            }
+            music.send(MusicEvent::Stop);
         }
     }
 }
 
-fn startup(audio:Res<bevy_kira_audio::Audio>) {
-    audio.set_volume_in_channel(0.5, &AudioChannel::new("music".into()));
+// applies the volume of the mixer to the interface and music one-shot channels, the other channels are set per sound
+fn mixer_system(audio:Res<bevy_kira_audio::Audio>, mixer:Res<AudioMixer>, mut paused:Local<bool>) {
+    if mixer.is_changed() {
+        audio.set_volume_in_channel(mixer.volume(AudioBus::Ui), &channel(AudioBus::Ui, 0));
+        audio.set_volume_in_channel(mixer.volume(AudioBus::Music), &channel(AudioBus::Music, 0));
+    }
+
+    if mixer.paused != *paused {
+        *paused = mixer.paused;
+        let mut channels:Vec<AudioChannel> = (0..SFX_CHANNELS).map(|i| channel(AudioBus::Sfx, i)).collect();
+        channels.push(channel(AudioBus::Ui, 0));
+        channels.push(channel(AudioBus::Music, 0));
+        channels.extend((0..2).map(music_channel));
+        for c in channels.iter() {
+            if mixer.paused {
+                audio.pause_channel(c);
+            } else {
+                audio.resume_channel(c);
+            }
+        }
+    }
 }
 
 impl Plugin for AudioPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_plugin(bevy_kira_audio::AudioPlugin);
-        app.add_startup_system(startup.system());
         app.add_event::<PlayAudioEvent>();
-        app.add_system_to_stage(CoreStage::PostUpdate, player.system());
+        app.add_event::<MusicEvent>();
+        app.init_resource::<AudioMixer>();
+        app.init_resource::<SoundBank>();
+        app.insert_resource(MusicDirector::default());
+        app.add_system_to_stage(CoreStage::PostUpdate, mixer_system.system());
+        app.add_system_to_stage(CoreStage::PostUpdate, player.system().label("audio_player"));
+        app.add_system_to_stage(CoreStage::PostUpdate, music_system.system().after("audio_player"));
     }
-}
\ No newline at end of file
+}
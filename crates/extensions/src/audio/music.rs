@@ -0,0 +1,162 @@
+use bevy::prelude::*;
+use bevy_kira_audio::AudioChannel;
+use serde::{Serialize, Deserialize};
+
+use super::{AudioBus, AudioMixer};
+
+// tracks to pick from while calm and while in combat, tracks loop until the music changes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Playlist {
+    pub calm:Vec<String>,
+    pub intense:Vec<String>
+}
+
+impl Playlist {
+    pub fn single(path:&str) -> Self {
+        Self {
+            calm:vec![path.into()],
+            intense:Vec::new()
+        }
+    }
+}
+
+pub enum MusicEvent {
+    Play(Playlist),
+    Stop,
+    // switches between the calm and intense tracks
+    Intensity(bool)
+}
+
+#[derive(Debug, Clone)]
+struct MusicTrack {
+    channel:usize,
+    path:String
+}
+
+pub struct MusicDirector {
+    // seconds a crossfade takes
+    pub fade_time:f32,
+    playlist:Playlist,
+    intense:bool,
+    next_calm:usize,
+    next_intense:usize,
+    active:Option<MusicTrack>,
+    fading:Option<MusicTrack>,
+    fade:f32
+}
+
+impl Default for MusicDirector {
+    fn default() -> Self {
+        Self {
+            fade_time:2.0,
+            playlist:Playlist::default(),
+            intense:false,
+            next_calm:0,
+            next_intense:0,
+            active:None,
+            fading:None,
+            fade:1.0
+        }
+    }
+}
+
+impl MusicDirector {
+    pub fn is_intense(&self) -> bool {
+        self.intense
+    }
+
+    // next track to play, intense tracks fall back to the calm ones
+    fn next_track(&mut self) -> Option<String> {
+        if self.intense && self.playlist.intense.len() > 0 {
+            let track = self.playlist.intense[self.next_intense % self.playlist.intense.len()].clone();
+            self.next_intense += 1;
+            Some(track)
+        } else if self.playlist.calm.len() > 0 {
+            let track = self.playlist.calm[self.next_calm % self.playlist.calm.len()].clone();
+            self.next_calm += 1;
+            Some(track)
+        } else {
+            None
+        }
+    }
+}
+
+pub(super) fn music_channel(index:usize) -> AudioChannel {
+    AudioChannel::new(format!("music_{}", index))
+}
+
+fn crossfade(director:&mut MusicDirector, track:Option<String>, audio:&bevy_kira_audio::Audio, asset_server:&AssetServer) {
+    if director.active.as_ref().map(|a| &a.path) == track.as_ref() {
+        return;
+    }
+
+    // a fade already in progress is cut short
+    if let Some(fading) = director.fading.take() {
+        audio.stop_channel(&music_channel(fading.channel));
+    }
+
+    let channel = director.active.as_ref().map(|a| 1 - a.channel).unwrap_or(0);
+    director.fading = director.active.take();
+    director.fade = 0.0;
+    if let Some(path) = track {
+        audio.set_volume_in_channel(0.0, &music_channel(channel));
+        audio.play_looped_in_channel(asset_server.load(path.as_str()), &music_channel(channel));
+        director.active = Some(MusicTrack { channel, path });
+    }
+}
+
+pub(super) fn music_system(mut director:ResMut<MusicDirector>,
+    mut events:EventReader<MusicEvent>,
+    audio:Res<bevy_kira_audio::Audio>,
+    asset_server:Res<AssetServer>,
+    mixer:Res<AudioMixer>,
+    time:Res<Time>) {
+    for e in events.iter() {
+        match e {
+            MusicEvent::Play(playlist) => {
+                if director.playlist != *playlist {
+                    director.playlist = playlist.clone();
+                    director.next_calm = 0;
+                    director.next_intense = 0;
+                }
+                director.intense = false;
+                let track = director.next_track();
+                crossfade(&mut director, track, &audio, &asset_server);
+            }
+            MusicEvent::Stop => {
+                crossfade(&mut director, None, &audio, &asset_server);
+            }
+            MusicEvent::Intensity(intense) => {
+                if director.intense != *intense {
+                    director.intense = *intense;
+                    if director.playlist.intense.len() == 0 {
+                        continue;
+                    }
+                    let track = director.next_track();
+                    crossfade(&mut director, track, &audio, &asset_server);
+                }
+            }
+        }
+    }
+
+    let volume = mixer.volume(AudioBus::Music);
+    if director.fade < 1.0 {
+        director.fade = (director.fade + time.delta_seconds() / director.fade_time.max(0.01)).min(1.0);
+        if let Some(active) = &director.active {
+            audio.set_volume_in_channel(director.fade * volume, &music_channel(active.channel));
+        }
+        if let Some(fading) = &director.fading {
+            audio.set_volume_in_channel((1.0 - director.fade) * volume, &music_channel(fading.channel));
+        }
+        if director.fade >= 1.0 {
+            if let Some(fading) = director.fading.take() {
+                audio.stop_channel(&music_channel(fading.channel));
+            }
+        }
+    } else if mixer.is_changed() {
+        if let Some(active) = &director.active {
+            audio.set_volume_in_channel(volume, &music_channel(active.channel));
+        }
+    }
+}
@@ -1,39 +1,129 @@
-use std::usize;
+use std::{collections::HashSet, usize};
+
+use bevy::{prelude::*, utils::HashMap};
+
+// width and height of a chunk in tiles
+pub const CHUNK_SIZE:usize = 32;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Terrain {
+    Normal,
+    Mud,
+    Ice,
+    Road,
+    Water
+}
+
+impl Default for Terrain {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+impl From<&str> for Terrain {
+    fn from(str: &str) -> Self {
+        match str.to_lowercase().as_str() {
+            "mud" => Self::Mud,
+            "ice" => Self::Ice,
+            "road" => Self::Road,
+            "water" => Self::Water,
+            _ => Self::Normal
+        }
+    }
+}
 
 #[derive(Copy, Clone)]
 pub struct Tile {
     pub index:u32,
-    pub solid:bool
+    pub solid:bool,
+    pub terrain:Terrain,
+    pub flip_h:bool,
+    pub flip_v:bool,
+    // swaps x and y, applied before flip_h and flip_v
+    pub flip_d:bool
 }
 
 impl Default for Tile {
     fn default() -> Self {
         Tile {
             index:0,
-            solid:false
+            solid:false,
+            terrain:Terrain::Normal,
+            flip_h:false,
+            flip_v:false,
+            flip_d:false
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct TileFrame {
+    pub index:u32,
+    pub duration:f32
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TileAnimation {
+    pub frames:Vec<TileFrame>
+}
+
+impl TileAnimation {
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|f| f.duration).sum()
+    }
+
+    // returns the sheet index to show at the given time in seconds
+    pub fn index_at(&self, time:f32) -> Option<u32> {
+        let duration = self.duration();
+        if duration <= 0.0 {
+            return self.frames.first().map(|f| f.index);
+        }
+
+        let mut t = time % duration;
+        for frame in self.frames.iter() {
+            if t < frame.duration {
+                return Some(frame.index);
+            }
+            t -= frame.duration;
         }
+
+        self.frames.last().map(|f| f.index)
     }
 }
 
 pub struct Tilemap {
     tiles:Vec<Tile>,
+    width:usize,
+    height:usize,
     sheet_size:u32,
-    texture_path:String
+    texture_path:String,
+    dirty:HashSet<(usize, usize)>,
+    animations:HashMap<u32, TileAnimation>
 }
 
 
 impl Tilemap {
     pub fn new(size:usize, sheet_size:u32, texture_name:&str) -> Tilemap {
-        let g = Tilemap {
-            tiles:vec![Tile::default(); size * size],
+        Self::with_size(size, size, sheet_size, texture_name)
+    }
+
+    pub fn with_size(width:usize, height:usize, sheet_size:u32, texture_name:&str) -> Tilemap {
+        let mut g = Tilemap {
+            tiles:vec![Tile::default(); width * height],
+            width,
+            height,
             sheet_size,
-            texture_path:texture_name.into()
+            texture_path:texture_name.into(),
+            dirty:HashSet::default(),
+            animations:HashMap::default()
         };
 
+        g.mark_all_dirty();
         return g;
     }
 
     pub fn tiles_mut(&mut self) -> &mut[Tile] {
+        self.mark_all_dirty();
         &mut self.tiles
     }
 
@@ -41,20 +131,39 @@ impl Tilemap {
         &self.tiles
     }
 
-    pub fn size(&self) -> usize {
-        f32::sqrt(self.tiles.len() as f32) as usize
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
     }
 
     pub fn set_tile(&mut self, tile:Tile, x:usize, y:usize) {
-        let size = self.size();
-        self.tiles[y * size + x] = tile;
+        if x < self.width && y < self.height {
+            self.tiles[y * self.width + x] = tile;
+            self.dirty.insert((x / CHUNK_SIZE, y / CHUNK_SIZE));
+        }
     }
 
     pub fn get_tile(&self, x:usize, y:usize) -> Option<&Tile> {
-        let c = self.tiles.get(y * self.size() + x);
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        let c = self.tiles.get(y * self.width + x);
         return c;
     }
 
+    // returns the tile at the given world position, if any
+    pub fn get_tile_at(&self, p:Vec2) -> Option<&Tile> {
+        if p.x < 0.0 || p.y < 0.0 {
+            return None;
+        }
+
+        self.get_tile(p.x as usize, p.y as usize)
+    }
+
     pub fn texture_path(&self) -> &str {
         &self.texture_path
     }
@@ -62,4 +171,69 @@ impl Tilemap {
     pub fn sheet_size(&self) -> u32 {
         self.sheet_size
     }
-}
\ No newline at end of file
+
+    // animates all tiles with the given sheet index
+    pub fn set_animation(&mut self, index:u32, animation:TileAnimation) {
+        self.animations.insert(index, animation);
+        self.mark_all_dirty();
+    }
+
+    pub fn animations(&self) -> &HashMap<u32, TileAnimation> {
+        &self.animations
+    }
+
+    pub fn is_animated(&self, tile:&Tile) -> bool {
+        self.animations.contains_key(&tile.index)
+    }
+
+    // returns the sheet index of the tile to show at the given time in seconds
+    pub fn animated_index(&self, tile:&Tile, time:f32) -> u32 {
+        self.animations.get(&tile.index).and_then(|a| a.index_at(time)).unwrap_or(tile.index)
+    }
+
+    pub fn chunks_x(&self) -> usize {
+        (self.width + CHUNK_SIZE - 1) / CHUNK_SIZE
+    }
+
+    pub fn chunks_y(&self) -> usize {
+        (self.height + CHUNK_SIZE - 1) / CHUNK_SIZE
+    }
+
+    // returns the tile range (x0, y0, x1, y1) covered by the chunk, end exclusive
+    pub fn chunk_bounds(&self, chunk_x:usize, chunk_y:usize) -> (usize, usize, usize, usize) {
+        let x0 = chunk_x * CHUNK_SIZE;
+        let y0 = chunk_y * CHUNK_SIZE;
+        (x0, y0, (x0 + CHUNK_SIZE).min(self.width), (y0 + CHUNK_SIZE).min(self.height))
+    }
+
+    pub fn mark_all_dirty(&mut self) {
+        for y in 0..self.chunks_y() {
+            for x in 0..self.chunks_x() {
+                self.dirty.insert((x, y));
+            }
+        }
+    }
+
+    pub fn has_dirty(&self) -> bool {
+        self.dirty.len() > 0
+    }
+
+    // returns the chunks which have changed since last call
+    pub fn take_dirty(&mut self) -> Vec<(usize, usize)> {
+        self.dirty.drain().collect()
+    }
+}
+
+// a chunk of a tilemap with its own mesh and collider
+pub struct TilemapChunk {
+    pub x:usize,
+    pub y:usize
+}
+
+// chunk entities spawned for a tilemap
+pub struct TilemapChunks {
+    pub material:Handle<StandardMaterial>,
+    pub chunks:HashMap<(usize, usize), Entity>,
+    // chunks containing animated tiles
+    pub animated:HashSet<(usize, usize)>
+}
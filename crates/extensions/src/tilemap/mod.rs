@@ -15,7 +15,7 @@ pub struct TilemapPlugin;
 impl Plugin for TilemapPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app
-        .add_system_to_stage(CoreStage::PreUpdate, tilemap_added_system.system())
-        .add_system_to_stage(CoreStage::PreUpdate, tilemap_update_system.system());
+        .add_system_to_stage(CoreStage::PreUpdate, tilemap_system.system())
+        .add_system(tilemap_animation_system.system());
     }
 }
\ No newline at end of file
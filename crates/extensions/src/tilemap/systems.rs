@@ -1,117 +1,239 @@
-use bevy::{ecs::system::EntityCommands, math::vec2, prelude::*, render::{mesh::Indices, pipeline::PrimitiveTopology}};
+use bevy::{math::vec2, prelude::*, render::{mesh::Indices, pipeline::PrimitiveTopology}, utils::{HashMap, HashSet}};
 use bevy_rapier2d::{na::Isometry2, rapier::{dynamics::RigidBodyBuilder, geometry::{ColliderBuilder, SharedShape}}};
 
-use super::Tilemap;
+use super::{Tilemap, TilemapChunk, TilemapChunks};
 
-pub fn tilemap_added_system(
-    mut commands:Commands, 
-    tilemaps: Query<(Entity,&mut Tilemap), Added<Tilemap>>,
-    asset_server:ResMut<AssetServer>,
+pub fn tilemap_system(
+    mut commands:Commands,
+    tilemaps: Query<(Entity, &mut Tilemap, Option<&mut TilemapChunks>), Changed<Tilemap>>,
+    asset_server:Res<AssetServer>,
     mut meshes:ResMut<Assets<Mesh>>,
-    mut materials:ResMut<Assets<StandardMaterial>>
-
+    mut materials:ResMut<Assets<StandardMaterial>>,
+    time:Res<Time>
 ) {
-    tilemaps.for_each_mut(|(e, mut tilemap)| {
-        let texture_handle:Handle<Texture> = asset_server.load(tilemap.texture_path());
-        let material_handle = materials.add(StandardMaterial {
-            base_color_texture: Some(texture_handle.clone()),
-            unlit:true,
-            ..Default::default()
-        });
-
-
-        let m:Mesh = Mesh::new(PrimitiveTopology::TriangleList);
-        let m = meshes.add(m);
-        update_tilemap_mesh(meshes.get_mut(m.clone()).unwrap(), &mut tilemap);
-
-        let mut e = commands.entity(e);
-        e.insert_bundle(PbrBundle {
-            mesh:m.clone(),
-            material:material_handle,
-            ..Default::default()
-        });
-
-        update_tilemap_collisions(&mut tilemap, &mut e);
+    let time = time.seconds_since_startup() as f32;
+    tilemaps.for_each_mut(|(e, mut tilemap, chunks)| {
+        match chunks {
+            Some(mut chunks) => {
+                // only rebuild chunks which have changed
+                if tilemap.has_dirty() {
+                    for (x, y) in tilemap.take_dirty() {
+                        if let Some(chunk) = chunks.chunks.remove(&(x, y)) {
+                            commands.entity(chunk).despawn_recursive();
+                        }
+
+                        let chunk = spawn_chunk(&mut commands, &tilemap, x, y, chunks.material.clone(), &mut meshes, time);
+                        commands.entity(e).push_children(&[chunk]);
+                        chunks.chunks.insert((x, y), chunk);
+                        set_animated(&mut chunks.animated, &tilemap, x, y);
+                    }
+                }
+            }
+            None => {
+                let texture_handle:Handle<Texture> = asset_server.load(tilemap.texture_path());
+                let material_handle = materials.add(StandardMaterial {
+                    base_color_texture: Some(texture_handle.clone()),
+                    unlit:true,
+                    ..Default::default()
+                });
+
+                let mut chunks = TilemapChunks {
+                    material:material_handle,
+                    chunks:HashMap::default(),
+                    animated:HashSet::default()
+                };
+
+                tilemap.take_dirty();
+                for y in 0..tilemap.chunks_y() {
+                    for x in 0..tilemap.chunks_x() {
+                        let chunk = spawn_chunk(&mut commands, &tilemap, x, y, chunks.material.clone(), &mut meshes, time);
+                        chunks.chunks.insert((x, y), chunk);
+                        set_animated(&mut chunks.animated, &tilemap, x, y);
+                    }
+                }
+
+                let children:Vec<Entity> = chunks.chunks.values().copied().collect();
+                commands.entity(e)
+                .insert(Transform::default())
+                .insert(GlobalTransform::default())
+                .insert(chunks)
+                .push_children(&children);
+            }
+        }
     });
 }
 
-fn update_tilemap_collisions(tilemap:&Tilemap, e:&mut EntityCommands) {
-    let size = tilemap.size();
-    let mut shapes = Vec::new();
-    for y in 0..size {
-        for x in 0..size {
-            if tilemap.get_tile(x, y).unwrap().solid {
-                shapes.push((Isometry2::new([x as f32 + 0.5, y as f32 + 0.5].into(), 0.0), SharedShape::cuboid(0.5, 0.5)));
+// updates the uvs of chunks with animated tiles, collisions are left untouched
+pub fn tilemap_animation_system(tilemaps:Query<(&Tilemap, &TilemapChunks)>, chunks:Query<&Handle<Mesh>, With<TilemapChunk>>, mut meshes:ResMut<Assets<Mesh>>, time:Res<Time>, mut last_time:Local<f32>) {
+    let time = time.seconds_since_startup() as f32;
+    tilemaps.for_each(|(tilemap, tilemap_chunks)| {
+        // only update when some animation has advanced to another frame
+        let changed = tilemap.animations().values().any(|a| a.index_at(time) != a.index_at(*last_time));
+        if !changed {
+            return;
+        }
+
+        for (x, y) in tilemap_chunks.animated.iter() {
+            if let Some(chunk) = tilemap_chunks.chunks.get(&(*x, *y)) {
+                if let Ok(mesh) = chunks.get(*chunk) {
+                    if let Some(mesh) = meshes.get_mut(mesh) {
+                        mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, tilemap_uvs(tilemap, *x, *y, time));
+                    }
+                }
             }
         }
+    });
+    *last_time = time;
+}
+
+fn set_animated(animated:&mut HashSet<(usize, usize)>, tilemap:&Tilemap, chunk_x:usize, chunk_y:usize) {
+    let (x0, y0, x1, y1) = tilemap.chunk_bounds(chunk_x, chunk_y);
+    let any = (y0..y1).any(|y| (x0..x1).any(|x| tilemap.is_animated(tilemap.get_tile(x, y).unwrap())));
+    if any {
+        animated.insert((chunk_x, chunk_y));
+    } else {
+        animated.remove(&(chunk_x, chunk_y));
+    }
+}
+
+fn spawn_chunk(commands:&mut Commands, tilemap:&Tilemap, x:usize, y:usize, material:Handle<StandardMaterial>, meshes:&mut Assets<Mesh>, time:f32) -> Entity {
+    let mut m:Mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    update_tilemap_mesh(&mut m, tilemap, x, y, time);
+
+    let mut e = commands.spawn_bundle(PbrBundle {
+        mesh:meshes.add(m),
+        material,
+        ..Default::default()
+    });
+    e.insert(TilemapChunk { x, y });
+
+    if let Some(collider) = tilemap_collider(tilemap, x, y) {
+        e.insert(RigidBodyBuilder::new_static());
+        e.insert(collider);
     }
 
+    e.id()
+}
+
+fn tilemap_collider(tilemap:&Tilemap, chunk_x:usize, chunk_y:usize) -> Option<ColliderBuilder> {
+    let shapes:Vec<_> = merge_solid_tiles(tilemap, chunk_x, chunk_y).iter().map(|(x, y, w, h)| {
+        let (hw, hh) = (*w as f32 / 2.0, *h as f32 / 2.0);
+        (Isometry2::new([*x as f32 + hw, *y as f32 + hh].into(), 0.0), SharedShape::cuboid(hw, hh))
+    }).collect();
+
     if shapes.len() > 0 {
         let compound = SharedShape::compound(shapes);
-        let collider = ColliderBuilder::new(compound);
-        let rigid_body = RigidBodyBuilder::new_static();
-        e.insert(rigid_body);
-        e.insert(collider);
+        return Some(ColliderBuilder::new(compound));
+    }
+
+    None
+}
+
+// greedily merges the solid tiles of a chunk into as few rectangles (x, y, width, height) as possible.
+// rectangles are grown along x first and then along y, and never overlap
+pub fn merge_solid_tiles(tilemap:&Tilemap, chunk_x:usize, chunk_y:usize) -> Vec<(usize, usize, usize, usize)> {
+    let (x0, y0, x1, y1) = tilemap.chunk_bounds(chunk_x, chunk_y);
+    let w = x1 - x0;
+    let mut merged = vec![false; w * (y1 - y0)];
+    let solid = |x:usize, y:usize, merged:&[bool]| {
+        tilemap.get_tile(x, y).unwrap().solid && !merged[(y - y0) * w + x - x0]
+    };
+
+    let mut rects = Vec::new();
+    for y in y0..y1 {
+        for x in x0..x1 {
+            if !solid(x, y, &merged) {
+                continue;
+            }
+
+            let mut end_x = x + 1;
+            while end_x < x1 && solid(end_x, y, &merged) {
+                end_x += 1;
+            }
+
+            let mut end_y = y + 1;
+            while end_y < y1 && (x..end_x).all(|x| solid(x, end_y, &merged)) {
+                end_y += 1;
+            }
+
+            for my in y..end_y {
+                for mx in x..end_x {
+                    merged[(my - y0) * w + mx - x0] = true;
+                }
+            }
+
+            rects.push((x, y, end_x - x, end_y - y));
+        }
     }
+
+    rects
 }
 
-fn update_tilemap_mesh(m:&mut Mesh, tilemap:&Tilemap) {
+fn tilemap_uvs(tilemap:&Tilemap, chunk_x:usize, chunk_y:usize, time:f32) -> Vec<[f32; 2]> {
+    let mut uvs = Vec::<[f32; 2]>::new();
+    let (x0, y0, x1, y1) = tilemap.chunk_bounds(chunk_x, chunk_y);
+    let sheet_size = tilemap.sheet_size();
+    let tex_w = 1.0 / sheet_size as f32;
+    let tex_h = 1.0 / sheet_size as f32;
+
+    // alpha is used to shift the texture samples 'a bit inwards' to avoid artifacts when rendering
+    // different resolutions now power of 2
+    let alpha = 0.001;
+
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let cell = tilemap.get_tile(x, y).expect("tilemap was out of bounds");
+            let index = tilemap.animated_index(cell, time);
+            let u = (index % sheet_size) as f32 * tex_w;
+            let v = (index / sheet_size) as f32 * tex_h;
+
+            // corners in texture space with y pointing down, in the order south west, north west, north east, south east
+            let corners = [(0.0, 1.0), (0.0, 0.0), (1.0, 0.0), (1.0, 1.0)];
+            for (tx, ty) in corners.iter() {
+                let (mut tx, mut ty) = (*tx, *ty);
+                if cell.flip_h {
+                    tx = 1.0 - tx;
+                }
+                if cell.flip_v {
+                    ty = 1.0 - ty;
+                }
+                if cell.flip_d {
+                    std::mem::swap(&mut tx, &mut ty);
+                }
+
+                uvs.push([u + alpha + tx * (tex_w - alpha * 2.0), v + alpha + ty * (tex_h - alpha * 2.0)]);
+            }
+        }
+    }
+
+    uvs
+}
+
+fn update_tilemap_mesh(m:&mut Mesh, tilemap:&Tilemap, chunk_x:usize, chunk_y:usize, time:f32) {
     let mut positions = Vec::<[f32; 3]>::new();
     let mut normals = Vec::<[f32; 3]>::new();
-    let mut uvs = Vec::<[f32; 2]>::new();
     let mut indicies:Vec<u32> = Vec::new();
 
-    let size = tilemap.size();
+    let (x0, y0, x1, y1) = tilemap.chunk_bounds(chunk_x, chunk_y);
     let mut i = 0;
     let scale = 1.0;
-    for y in 0..size {
-        for x in 0..size {
-            let index = y * size + x;
-            let cell = tilemap.tiles().get(index).expect("tilemap was out of bounds");
+    for y in y0..y1 {
+        for x in x0..x1 {
             let north_west = vec2(x as f32 * scale, y as f32 * scale + scale);
             let north_east = vec2(x as f32 * scale + scale, y as f32 * scale + scale);
             let south_west = vec2(x as f32 * scale,  y as f32 * scale);
             let south_east = vec2(x as f32 * scale + scale, y as f32 * scale);
 
-            let sheet_size = tilemap.sheet_size();
-            let tex_w = 1.0 / sheet_size as f32;
-            let tex_h = 1.0 / sheet_size as f32;
-            let x = (cell.index % sheet_size) as f32; 
-            let y = (cell.index / sheet_size) as f32;
-
-            // alpha is used to shift the texture samples 'a bit inwards' to avoid artifacts when rendering
-            // different resolutions now power of 2
-            let alpha = 0.001;
-            let u = x * tex_w;
-            let v = y * tex_h;
-
             let vertices = [
-                (
-                    [south_west.x, south_west.y, 0.0],
-                    [0.0, 0.0, 1.0],
-                    [u + alpha, v+tex_h - alpha],
-                ),
-                (
-                    [north_west.x, north_west.y, 0.0],
-                    [0.0, 0.0, 1.0],
-                    [u + alpha, v + alpha],
-                ),
-                (
-                    [north_east.x, north_east.y, 0.0],
-                    [0.0, 0.0, 1.0],
-                    [u+tex_w - alpha, v + alpha],
-                ),
-                (
-                    [south_east.x, south_east.y, 0.0],
-                    [0.0, 0.0, 1.0],
-                    [u+tex_w - alpha, v+tex_h - alpha],
-                ),
+                [south_west.x, south_west.y, 0.0],
+                [north_west.x, north_west.y, 0.0],
+                [north_east.x, north_east.y, 0.0],
+                [south_east.x, south_east.y, 0.0],
             ];
-        
-            for (position, normal, uv) in vertices.iter() {
+
+            for position in vertices.iter() {
                 positions.push(*position);
-                normals.push(*normal);
-                uvs.push(*uv);
+                normals.push([0.0, 0.0, 1.0]);
             }
 
             indicies.push(i + 0);
@@ -127,15 +249,91 @@ fn update_tilemap_mesh(m:&mut Mesh, tilemap:&Tilemap) {
 
     m.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
     m.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
-    m.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    m.set_attribute(Mesh::ATTRIBUTE_UV_0, tilemap_uvs(tilemap, chunk_x, chunk_y, time));
     m.set_indices(Some(Indices::U32(indicies)));
 }
 
-pub fn tilemap_update_system(tilemaps: Query<(Entity,&mut Tilemap, &Handle<Mesh>), Changed<Tilemap>>, mut commands:Commands, mut meshes:ResMut<Assets<Mesh>>) {
-    tilemaps.for_each_mut(|(e, tilemap, mesh)| {
-        let mut e = commands.entity(e);
-        update_tilemap_collisions(&tilemap, &mut e);
-        let mesh = meshes.get_mut(mesh).expect("mesh was not found for tilemap");
-        update_tilemap_mesh(mesh, &tilemap);
-    });
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tilemap::{Tile, CHUNK_SIZE};
+
+    fn tilemap(width:usize, height:usize, solid:impl Fn(usize, usize) -> bool) -> Tilemap {
+        let mut tilemap = Tilemap::with_size(width, height, 8, "test.png");
+        for y in 0..height {
+            for x in 0..width {
+                tilemap.set_tile(Tile { solid:solid(x, y), ..Default::default() }, x, y);
+            }
+        }
+        tilemap
+    }
+
+    // merges every chunk and checks that each solid tile is covered by exactly one rectangle and nothing else is
+    fn assert_covers_solid(tilemap:&Tilemap) {
+        let mut covered = vec![0; tilemap.width() * tilemap.height()];
+        for chunk_y in 0..tilemap.chunks_y() {
+            for chunk_x in 0..tilemap.chunks_x() {
+                let (x0, y0, x1, y1) = tilemap.chunk_bounds(chunk_x, chunk_y);
+                for (x, y, w, h) in merge_solid_tiles(tilemap, chunk_x, chunk_y) {
+                    assert!(w > 0 && h > 0, "empty rectangle at {}, {}", x, y);
+                    assert!(x >= x0 && y >= y0 && x + w <= x1 && y + h <= y1, "rectangle at {}, {} leaves its chunk", x, y);
+                    for ty in y..y + h {
+                        for tx in x..x + w {
+                            covered[ty * tilemap.width() + tx] += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        for y in 0..tilemap.height() {
+            for x in 0..tilemap.width() {
+                let expected = if tilemap.get_tile(x, y).unwrap().solid { 1 } else { 0 };
+                assert_eq!(covered[y * tilemap.width() + x], expected, "tile {}, {}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn empty_map() {
+        let tilemap = tilemap(10, 7, |_, _| false);
+        assert_covers_solid(&tilemap);
+        assert!(merge_solid_tiles(&tilemap, 0, 0).is_empty());
+    }
+
+    #[test]
+    fn full_chunk() {
+        let tilemap = tilemap(CHUNK_SIZE, CHUNK_SIZE, |_, _| true);
+        assert_covers_solid(&tilemap);
+        assert_eq!(merge_solid_tiles(&tilemap, 0, 0), vec![(0, 0, CHUNK_SIZE, CHUNK_SIZE)]);
+    }
+
+    #[test]
+    fn l_shape() {
+        let tilemap = tilemap(8, 8, |x, y| x == 1 || y == 6);
+        assert_covers_solid(&tilemap);
+    }
+
+    #[test]
+    fn u_shape() {
+        let tilemap = tilemap(9, 9, |x, y| (x == 1 || x == 7 || y == 7) && x >= 1 && x <= 7 && y >= 1 && y <= 7);
+        assert_covers_solid(&tilemap);
+    }
+
+    #[test]
+    fn checkerboard() {
+        let tilemap = tilemap(12, 11, |x, y| (x + y) % 2 == 0);
+        assert_covers_solid(&tilemap);
+        assert_eq!(merge_solid_tiles(&tilemap, 0, 0).len(), (12 * 11 + 1) / 2);
+    }
+
+    #[test]
+    fn across_chunk_border() {
+        // a block straddling the corner where four chunks meet, in a map that does not end on a chunk border
+        let (lo, hi) = (CHUNK_SIZE - 3, CHUNK_SIZE + 4);
+        let tilemap = tilemap(CHUNK_SIZE + 10, CHUNK_SIZE + 7, |x, y| (x >= lo && x < hi && y >= lo && y < hi) || x == CHUNK_SIZE + 9);
+        assert_covers_solid(&tilemap);
+        assert_eq!(tilemap.chunks_x(), 2);
+        assert_eq!(tilemap.chunks_y(), 2);
+    }
+}
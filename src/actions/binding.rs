@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton)
+}
+
+macro_rules! key_codes {
+    ($($key:ident),*) => {
+        fn key_code_from_str(str:&str) -> Option<KeyCode> {
+            match str {
+                $(stringify!($key) => Some(KeyCode::$key),)*
+                _ => None
+            }
+        }
+    };
+}
+
+key_codes!(
+    Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0,
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Escape, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    Insert, Home, Delete, End, PageDown, PageUp,
+    Left, Up, Right, Down,
+    Back, Return, Space, Tab, Grave, Minus, Equals, Comma, Period, Slash, Backslash, Semicolon, Apostrophe, LBracket, RBracket,
+    LAlt, LControl, LShift, RAlt, RControl, RShift,
+    Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9
+);
+
+impl Binding {
+    // parses bindings such as 'W', 'F1' or 'Mouse:Left'
+    pub fn parse(str:&str) -> Option<Binding> {
+        if let Some(button) = str.strip_prefix("Mouse:") {
+            return match button {
+                "Left" => Some(Binding::Mouse(MouseButton::Left)),
+                "Right" => Some(Binding::Mouse(MouseButton::Right)),
+                "Middle" => Some(Binding::Mouse(MouseButton::Middle)),
+                other => other.parse::<u16>().ok().map(|b| Binding::Mouse(MouseButton::Other(b)))
+            };
+        }
+
+        key_code_from_str(str).map(|k| Binding::Key(k))
+    }
+}
+
+impl ToString for Binding {
+    fn to_string(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("{:?}", key),
+            Binding::Mouse(MouseButton::Other(b)) => format!("Mouse:{}", b),
+            Binding::Mouse(button) => format!("Mouse:{:?}", button)
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use bevy::{input::InputSystem, prelude::*, utils::{HashMap, HashSet}};
+
+use crate::Config;
+
+mod binding;
+pub use binding::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    DriveForward,
+    DriveBackward,
+    TurnLeft,
+    TurnRight,
+    Fire,
+    DrawPath,
+    NewGame,
+    ToggleConsole,
+    ToggleSettings,
+    Pause,
+    ToggleRts,
+    Select,
+    MoveOrder,
+    AttackMoveModifier,
+    HoldOrder,
+    CycleFormation,
+    // continues from the splash screen and the end of a level
+    Confirm,
+    LoadMap(u8)
+}
+
+impl Action {
+    pub fn all() -> Vec<Action> {
+        let mut all = vec![
+            Action::DriveForward,
+            Action::DriveBackward,
+            Action::TurnLeft,
+            Action::TurnRight,
+            Action::Fire,
+            Action::DrawPath,
+            Action::NewGame,
+            Action::ToggleConsole,
+            Action::ToggleSettings,
+            Action::Pause,
+            Action::ToggleRts,
+            Action::Select,
+            Action::MoveOrder,
+            Action::AttackMoveModifier,
+            Action::HoldOrder,
+            Action::CycleFormation,
+            Action::Confirm
+        ];
+        for i in 1..=9 {
+            all.push(Action::LoadMap(i));
+        }
+        all
+    }
+
+    // name of the action as used in the bindings section of the config
+    pub fn name(&self) -> String {
+        match self {
+            Action::DriveForward => "drive_forward".into(),
+            Action::DriveBackward => "drive_backward".into(),
+            Action::TurnLeft => "turn_left".into(),
+            Action::TurnRight => "turn_right".into(),
+            Action::Fire => "fire".into(),
+            Action::DrawPath => "draw_path".into(),
+            Action::NewGame => "new_game".into(),
+            Action::ToggleConsole => "toggle_console".into(),
+            Action::ToggleSettings => "toggle_settings".into(),
+            Action::Pause => "pause".into(),
+            Action::ToggleRts => "toggle_rts".into(),
+            Action::Select => "select".into(),
+            Action::MoveOrder => "move_order".into(),
+            Action::AttackMoveModifier => "attack_move_modifier".into(),
+            Action::HoldOrder => "hold_order".into(),
+            Action::CycleFormation => "cycle_formation".into(),
+            Action::Confirm => "confirm".into(),
+            Action::LoadMap(i) => format!("load_map_{}", i)
+        }
+    }
+
+    pub fn default_bindings(&self) -> Vec<Binding> {
+        use Binding::*;
+        match self {
+            Action::DriveForward => vec![Key(KeyCode::W)],
+            Action::DriveBackward => vec![Key(KeyCode::S)],
+            Action::TurnLeft => vec![Key(KeyCode::A)],
+            Action::TurnRight => vec![Key(KeyCode::D)],
+            Action::Fire => vec![Mouse(MouseButton::Left)],
+            Action::DrawPath => vec![Mouse(MouseButton::Left)],
+            Action::NewGame => vec![Key(KeyCode::F5)],
+            Action::ToggleConsole => vec![Key(KeyCode::F1), Key(KeyCode::Grave)],
+            Action::ToggleSettings => vec![Key(KeyCode::F2)],
+            Action::Pause => vec![Key(KeyCode::Escape)],
+            Action::ToggleRts => vec![Key(KeyCode::Tab)],
+            Action::Select => vec![Mouse(MouseButton::Left)],
+            Action::MoveOrder => vec![Mouse(MouseButton::Right)],
+            Action::AttackMoveModifier => vec![Key(KeyCode::LShift)],
+            Action::HoldOrder => vec![Key(KeyCode::H)],
+            Action::CycleFormation => vec![Key(KeyCode::F)],
+            Action::Confirm => vec![Mouse(MouseButton::Left), Key(KeyCode::Return), Key(KeyCode::Space)],
+            Action::LoadMap(i) => {
+                let keys = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9];
+                keys.get(*i as usize - 1).map(|k| vec![Key(*k)]).unwrap_or_default()
+            }
+        }
+    }
+}
+
+pub struct Bindings {
+    pub bindings:HashMap<Action, Vec<Binding>>
+}
+
+impl Bindings {
+    // reads bindings from the config, falling back to the default bindings
+    pub fn from_config(config:&Config) -> Self {
+        let mut bindings = HashMap::default();
+        for action in Action::all() {
+            let mut parsed = Vec::new();
+            if let Some(value) = config.binding(&action.name()) {
+                for b in value.split(',').map(|b| b.trim()).filter(|b| b.len() > 0) {
+                    match Binding::parse(b) {
+                        Some(binding) => parsed.push(binding),
+                        None => warn!("unknown binding '{}' for {}", b, action.name())
+                    }
+                }
+
+                if parsed.len() == 0 {
+                    warn!("no valid bindings for {}, using the defaults", action.name());
+                }
+            }
+
+            if parsed.len() == 0 {
+                parsed = action.default_bindings();
+            }
+            bindings.insert(action, parsed);
+        }
+
+        Self {
+            bindings
+        }
+    }
+
+    pub fn get(&self, action:Action) -> &[Binding] {
+        self.bindings.get(&action).map(|b| b.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn set(&mut self, action:Action, bindings:Vec<Binding>, config:&mut Config) {
+        let value = bindings.iter().map(|b| b.to_string()).collect::<Vec<String>>().join(", ");
+        config.set_binding(&action.name(), &value);
+        self.bindings.insert(action, bindings);
+    }
+}
+
+// state of all actions, updated once per frame from the raw input
+#[derive(Default)]
+pub struct Actions {
+    pressed:HashSet<Action>,
+    just_pressed:HashSet<Action>,
+    just_released:HashSet<Action>
+}
+
+impl Actions {
+    pub fn pressed(&self, action:Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action:Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub fn just_released(&self, action:Action) -> bool {
+        self.just_released.contains(&action)
+    }
+}
+
+#[derive(Hash, Copy, Clone, PartialEq, Eq, Debug, SystemLabel)]
+pub struct ActionSystem;
+
+fn update_actions(mut actions:ResMut<Actions>, bindings:Res<Bindings>, keys:Res<Input<KeyCode>>, mouse:Res<Input<MouseButton>>, gamepad_buttons:Res<Input<GamepadButton>>, touches:Res<Touches>) {
+    actions.pressed.clear();
+    actions.just_pressed.clear();
+    actions.just_released.clear();
+    for (action, bindings) in bindings.bindings.iter() {
+        for binding in bindings {
+            let (pressed, just_pressed, just_released) = match binding {
+                Binding::Key(key) => (keys.pressed(*key), keys.just_pressed(*key), keys.just_released(*key)),
+                Binding::Mouse(button) => (mouse.pressed(*button), mouse.just_pressed(*button), mouse.just_released(*button))
+            };
+
+            if pressed {
+                actions.pressed.insert(*action);
+            }
+            if just_pressed {
+                actions.just_pressed.insert(*action);
+            }
+            if just_released {
+                actions.just_released.insert(*action);
+            }
+        }
+    }
+
+    // gamepads and touch screens can not be rebound, but must be able to continue
+    let gamepad_confirm = gamepad_buttons.get_just_pressed().any(|b| b.1 == GamepadButtonType::South || b.1 == GamepadButtonType::Start);
+    if gamepad_confirm || touches.iter_just_pressed().next().is_some() {
+        actions.just_pressed.insert(Action::Confirm);
+    }
+}
+
+fn startup(mut commands:Commands, config:Res<Config>) {
+    commands.insert_resource(Bindings::from_config(&config));
+}
+
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(Actions::default());
+        app.add_startup_system(startup.system());
+        app.add_system_to_stage(CoreStage::PreUpdate, update_actions.system().label(ActionSystem).after(InputSystem));
+    }
+}
@@ -0,0 +1,36 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::AssetCache;
+
+// group preloaded at startup, maps have a group of their own named after the map path
+pub const COMMON_GROUP:&str = "common";
+
+// asset paths grouped by what needs them, generated by build.rs
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct AssetManifest {
+    pub groups:HashMap<String, Vec<String>>,
+    #[serde(skip)]
+    pub ready:bool
+}
+
+impl AssetManifest {
+    // the manifest generated for this build
+    pub fn embedded() -> Self {
+        serde_json::from_str(include_str!(concat!(env!("OUT_DIR"), "/manifest.json"))).unwrap_or_default()
+    }
+
+    pub fn group(&self, name:&str) -> &[String] {
+        self.groups.get(name).map(|g| g.as_slice()).unwrap_or(&[])
+    }
+
+    // starts loading every asset of the group and tracks it in the cache
+    pub fn preload(&self, name:&str, asset_cache:&mut AssetCache, asset_server:&AssetServer) {
+        for path in self.group(name) {
+            asset_cache.track_untyped(&asset_server.load_untyped(path.as_str()));
+        }
+    }
+}
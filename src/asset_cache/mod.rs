@@ -1,21 +1,36 @@
 use std::collections::{HashSet};
-use bevy::{asset::Asset, prelude::*};
+use bevy::{app::AppExit, asset::{Asset, LoadState}, prelude::*};
+
+use crate::{Config, Console};
+
+mod manifest;
+pub use manifest::*;
+
+// an asset that could not be loaded
+#[derive(Debug, Clone)]
+pub struct FailedAsset {
+    pub handle:HandleUntyped,
+    pub path:String,
+    pub error:String
+}
 
 #[derive(Default)]
 pub struct AssetCache {
     loaded:HashSet<HandleUntyped>,
     pub(self) not_loaded:HashSet<HandleUntyped>,
+    failed:Vec<FailedAsset>
 }
 
 impl AssetCache {
     pub fn clear(&mut self) {
         self.loaded.clear();
         self.not_loaded.clear();
+        self.failed.clear();
     }
 
     pub fn contains<T:Asset>(&self, handle:&Handle<T>) -> bool {
         let handle = handle.clone_untyped();
-        self.loaded.contains(&handle) || self.not_loaded.contains(&handle)
+        self.loaded.contains(&handle) || self.not_loaded.contains(&handle) || self.failed.iter().any(|f| f.handle == handle)
     }
 
     pub fn is_loaded<T:Asset>(&self, handle:&Handle<T>) -> bool {
@@ -35,7 +50,7 @@ impl AssetCache {
     }
 
     pub fn count(&self) -> usize {
-        self.not_loaded.len() + self.loaded.len()
+        self.not_loaded.len() + self.loaded.len() + self.failed.len()
     }
 
     pub fn loaded_count(&self) -> usize {
@@ -46,21 +61,48 @@ impl AssetCache {
         self.not_loaded.len()
     }
 
+    pub fn failed(&self) -> &[FailedAsset] {
+        &self.failed
+    }
+
+    pub fn has_failed(&self) -> bool {
+        self.failed.len() > 0
+    }
+
+    // true when nothing is loading anymore, failed assets included
     pub fn all_is_loaded(&self) -> bool {
         self.not_loaded.len() == 0
     }
 }
 
-fn asset_loader(mut asset_cache:ResMut<AssetCache>, asset_server:Res<AssetServer>) {
+// bevy only logs the error of the asset loader, so all that can be told here is whether the file exists
+#[cfg(not(target_arch = "wasm32"))]
+fn failure_reason(path:&str) -> String {
+    if std::path::Path::new("assets").join(path).exists() {
+        "could not be loaded, see the log for the loader error".into()
+    } else {
+        "file not found".into()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn failure_reason(_path:&str) -> String {
+    "could not be loaded, see the browser console for the loader error".into()
+}
+
+fn asset_loader(mut asset_cache:ResMut<AssetCache>, asset_server:Res<AssetServer>, mut console:ResMut<Console>, config:Res<Config>, mut exit:EventWriter<AppExit>) {
     let mut loaded = Vec::new();
+    let mut failed = Vec::new();
     for handle in asset_cache.not_loaded.iter() {
         match asset_server.get_load_state(handle) {
-            bevy::asset::LoadState::NotLoaded => {},
-            bevy::asset::LoadState::Loading => {},
-            bevy::asset::LoadState::Loaded => {
+            LoadState::NotLoaded => {},
+            LoadState::Loading => {},
+            LoadState::Loaded => {
                 loaded.push(handle.clone());
             },
-            bevy::asset::LoadState::Failed => {},
+            LoadState::Failed => {
+                failed.push(handle.clone());
+            },
         }
     }
 
@@ -68,12 +110,28 @@ fn asset_loader(mut asset_cache:ResMut<AssetCache>, asset_server:Res<AssetServer
         asset_cache.not_loaded.remove(&handle);
         asset_cache.loaded.insert(handle);
     }
+
+    for handle in failed {
+        asset_cache.not_loaded.remove(&handle);
+        let path = asset_server.get_handle_path(&handle).map(|p| p.path().to_string_lossy().to_string()).unwrap_or_else(|| "unknown".into());
+        let error = failure_reason(&path);
+        if config.fail_fast() {
+            error!("failed to load {}: {}, exiting", path, error);
+            exit.send(AppExit);
+        } else {
+            warn!("failed to load {}: {}", path, error);
+        }
+
+        console.logln(format!("failed to load {}: {}", path, error));
+        asset_cache.failed.push(FailedAsset { handle, path, error });
+    }
 }
 
 pub struct AssetCachePlugin;
 impl Plugin for AssetCachePlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.insert_resource(AssetCache::default());
+        app.insert_resource(AssetManifest::default());
         app.add_system(asset_loader.system());
     }
 }
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use bevy::{asset::LoadState, prelude::*};
+use serde::Deserialize;
+
+use crate::{AssetCache, Bot, BotPreset, BotProperties, BotTuned, BotTuning, Config, Json};
+
+// difficulty and personality presets loaded from bots.json
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct BotPresets {
+    pub difficulties:HashMap<String, BotPreset>,
+    pub personalities:HashMap<String, BotPreset>,
+    #[serde(skip)]
+    pub ready:bool
+}
+
+impl BotPresets {
+    // tuning from the difficulty, then the personality and finally the overrides of a single bot
+    pub fn resolve(&self, difficulty:&str, personality:&str, overrides:&BotPreset) -> BotTuning {
+        let mut tuning = BotTuning::default();
+        match self.difficulties.get(difficulty) {
+            Some(preset) => tuning.apply(preset),
+            None => warn!("unknown bot difficulty '{}'", difficulty)
+        }
+        match self.personalities.get(personality) {
+            Some(preset) => tuning.apply(preset),
+            None => warn!("unknown bot personality '{}'", personality)
+        }
+        tuning.apply(overrides);
+        tuning
+    }
+}
+
+fn load_bot_presets(mut commands:Commands, mut presets:ResMut<BotPresets>, asset_server:Res<AssetServer>, json:Res<Assets<Json>>, mut asset_cache:ResMut<AssetCache>, mut events:EventReader<AssetEvent<Json>>, tuned:Query<Entity, With<BotTuned>>) {
+    // bots.json changed on disk, read it again and retune every bot
+    for e in events.iter() {
+        if let AssetEvent::Modified { handle } = e {
+            if *handle == asset_server.get_handle("bots.json") {
+                presets.ready = false;
+            }
+        }
+    }
+
+    if presets.ready {
+        return;
+    }
+
+    let handle:Handle<Json> = asset_server.get_handle("bots.json");
+    if asset_cache.contains(&handle) == false {
+        let handle:Handle<Json> = asset_server.load("bots.json");
+        asset_cache.track(&handle);
+    } else if asset_cache.is_loaded(&handle) {
+        let json = json.get(handle).unwrap();
+        match serde_json::from_value::<BotPresets>(json.0.clone()) {
+            Ok(loaded) => *presets = loaded,
+            Err(err) => warn!("could not parse bots.json: {}", err)
+        }
+        for e in tuned.iter() {
+            commands.entity(e).remove::<BotTuned>();
+        }
+        presets.ready = true;
+    } else if asset_server.get_load_state(&handle) == LoadState::Failed {
+        // bots still work with the default tuning
+        warn!("could not load bots.json");
+        presets.ready = true;
+    }
+}
+
+fn bot_tuning_system(mut commands:Commands, presets:Res<BotPresets>, config:Res<Config>, mut bots:Query<(Entity, &mut Bot, Option<&BotProperties>), Without<BotTuned>>) {
+    if presets.ready == false {
+        return;
+    }
+
+    for (e, mut bot, properties) in bots.iter_mut() {
+        let properties = properties.map(|p| p.properties.clone()).unwrap_or_default();
+        let difficulty = properties.get("difficulty").map(|d| d.to_lowercase()).unwrap_or_else(|| config.difficulty().to_lowercase());
+        let personality = properties.get("personality").map(|p| p.to_lowercase()).unwrap_or_else(|| "balanced".into());
+        let tuning = presets.resolve(&difficulty, &personality, &BotPreset::from_properties(&properties, &format!("{:?}", e)));
+        bot.perception.view_range = tuning.view_range;
+        commands.entity(e).insert(tuning).insert(BotTuned);
+    }
+}
+
+pub struct BotPresetsPlugin;
+
+impl Plugin for BotPresetsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(BotPresets::default());
+        app.add_system(load_bot_presets.system());
+        app.add_system(bot_tuning_system.system());
+    }
+}
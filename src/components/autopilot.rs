@@ -15,16 +15,43 @@ impl From<Vec3> for Waypoint {
     }
 }
 
-#[derive(Default, Clone, PartialEq)]
+#[derive(Clone, PartialEq)]
 pub struct Autopilot {
     pub waypoints:VecDeque<Waypoint>,
-    pub planning:bool
+    pub planning:bool,
+    // distance ahead on the path the controller steers towards
+    pub lookahead:f32,
+    // distance to the last waypoint at which the tank is considered to have arrived
+    pub goal_radius:f32,
+    // distance to the last waypoint at which the tank starts to slow down
+    pub slow_radius:f32,
+    // distance between waypoints when drawing a path
+    pub spacing:f32,
+    // true if the tank may reverse towards targets behind it
+    pub allow_reverse:bool,
+    pub reversing:bool
+}
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Self {
+            waypoints:VecDeque::default(),
+            planning:false,
+            lookahead:1.0,
+            goal_radius:0.25,
+            slow_radius:1.5,
+            spacing:0.5,
+            allow_reverse:true,
+            reversing:false
+        }
+    }
 }
 
 impl Autopilot {
     pub fn clear(&mut self) {
         self.waypoints.clear();
         self.planning = false;
+        self.reversing = false;
     }
 
     pub fn any_within_radius(&self, radius:f32, p:Vec3) -> bool {
@@ -35,4 +62,58 @@ impl Autopilot {
         }
         false
     }
-}
\ No newline at end of file
+
+    // length of the remaining path starting at p
+    pub fn remaining_distance(&self, p:Vec3) -> f32 {
+        let mut last = p;
+        let mut d = 0.0;
+        for w in &self.waypoints {
+            d += last.distance(w.location);
+            last = w.location;
+        }
+        d
+    }
+
+    // replaces the waypoints by a Catmull-Rom spline through them, resampled at the waypoint spacing
+    pub fn smooth(&mut self) {
+        if self.waypoints.len() < 3 {
+            return;
+        }
+
+        let points:Vec<Vec3> = self.waypoints.iter().map(|w| w.location).collect();
+        let mut smoothed = VecDeque::new();
+        let mut last = points[0];
+        smoothed.push_back(last.into());
+        for i in 0..points.len() - 1 {
+            let p0 = points[if i > 0 { i - 1 } else { 0 }];
+            let p1 = points[i];
+            let p2 = points[i + 1];
+            let p3 = points[(i + 2).min(points.len() - 1)];
+            let steps = ((p1.distance(p2) / self.spacing) * 4.0).ceil().max(1.0) as usize;
+            for s in 1..=steps {
+                let p = catmull_rom(p0, p1, p2, p3, s as f32 / steps as f32);
+                if p.distance(last) >= self.spacing {
+                    smoothed.push_back(p.into());
+                    last = p;
+                }
+            }
+        }
+
+        // always end exactly where the path was drawn to
+        let end = points[points.len() - 1];
+        if last != end {
+            smoothed.push_back(end.into());
+        }
+
+        self.waypoints = smoothed;
+    }
+}
+
+fn catmull_rom(p0:Vec3, p1:Vec3, p2:Vec3, p3:Vec3, t:f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * ((2.0 * p1)
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
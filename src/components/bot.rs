@@ -1,7 +1,11 @@
-use bevy::{math::{Vec3}, prelude::Entity};
+use std::f32::consts::PI;
+
+use bevy::{log::warn, math::{Vec3}, prelude::Entity, utils::HashMap};
 use rand::random;
 use serde::{Serialize, Deserialize};
 
+use crate::Faction;
+
 #[derive(Clone, Copy, Serialize, Deserialize, Debug)]
 pub enum BotState {
     Idle,
@@ -17,7 +21,33 @@ pub struct Bot {
     pub mem:[f32;4],
     pub sensors:BotSensors,
     pub attack_timer:f32,
-    pub trigger_timer:f32
+    pub trigger_timer:f32,
+    pub perception:Perception
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug)]
+pub struct Perception {
+    // half angle of the view cone in radians
+    pub view_angle:f32,
+    pub view_range:f32,
+    // enemies closer than this are noticed even outside the view cone
+    pub awareness_range:f32,
+    // seconds an enemy is remembered after it was last seen or heard
+    pub memory:f32,
+    // multiplier applied to the range of noises
+    pub hearing:f32
+}
+
+impl Default for Perception {
+    fn default() -> Self {
+        Self {
+            view_angle:PI / 3.0,
+            view_range:8.0,
+            awareness_range:1.5,
+            memory:5.0,
+            hearing:1.0
+        }
+    }
 }
 
 #[derive(Default, Clone, Serialize, Deserialize, Debug)]
@@ -26,11 +56,41 @@ pub struct BotSensors {
     pub obstacle_distance_front:f32,
     pub obstacle_distance_left:f32,
     pub obstacle_distance_right:f32,
+    // enemies seen, heard or reported by allies, with their last known position
     pub known_enemies:Vec<Enemy>,
-    pub visible_enemies:Vec<Enemy>
+    pub visible_enemies:Vec<Enemy>,
+    // position and time of the last noise heard
+    pub last_noise:Option<(Vec3, f64)>
 }
 
 impl BotSensors {
+    // updates the memory of an enemy if the information is newer than what is already known
+    pub fn remember(&mut self, enemy:Enemy) {
+        if let Some(known) = self.known_enemies.iter_mut().find(|e| e.entity == enemy.entity) {
+            if enemy.last_seen >= known.last_seen {
+                *known = enemy;
+            }
+        } else {
+            self.known_enemies.push(enemy);
+        }
+    }
+
+    // forgets enemies not seen or heard for longer than the memory lasts
+    pub fn forget(&mut self, now:f64, memory:f32) {
+        self.known_enemies.retain(|e| now - e.last_seen <= memory as f64);
+    }
+
+    pub fn get_most_recent_known_enemy(&self) -> Option<Enemy> {
+        let mut recent:Option<&Enemy> = None;
+        for e in &self.known_enemies {
+            if recent.map(|r| e.last_seen > r.last_seen).unwrap_or(true) {
+                recent = Some(e);
+            }
+        }
+
+        recent.copied()
+    }
+
     pub fn get_closest_visible_enemy(&self) -> Option<Enemy> {
         if self.visible_enemies.len() > 0 {
             let mut enemy = self.visible_enemies.first().unwrap();
@@ -51,7 +111,36 @@ impl BotSensors {
 pub struct Enemy {
     pub entity:Entity,
     pub position:Vec3,
-    pub distance:f32
+    pub velocity:Vec3,
+    pub distance:f32,
+    // time the enemy was last seen or heard
+    pub last_seen:f64
+}
+
+// enemies spotted by the bots of each faction, shared between allies
+#[derive(Default)]
+pub struct TeamIntel {
+    pub enemies:HashMap<Faction, HashMap<Entity, Enemy>>
+}
+
+impl TeamIntel {
+    pub fn report(&mut self, faction:Faction, enemy:Enemy) {
+        let enemies = self.enemies.entry(faction).or_insert_with(HashMap::default);
+        let newer = enemies.get(&enemy.entity).map(|e| enemy.last_seen >= e.last_seen).unwrap_or(true);
+        if newer {
+            enemies.insert(enemy.entity, enemy);
+        }
+    }
+
+    pub fn known(&self, faction:Faction) -> Vec<Enemy> {
+        self.enemies.get(&faction).map(|e| e.values().copied().collect()).unwrap_or_default()
+    }
+
+    pub fn forget(&mut self, now:f64, memory:f32) {
+        for enemies in self.enemies.values_mut() {
+            enemies.retain(|_, e| now - e.last_seen <= memory as f64);
+        }
+    }
 }
 
 
@@ -63,7 +152,147 @@ impl Default for Bot {
             mem:Default::default(),
             sensors:Default::default(),
             attack_timer:0.0,
-            trigger_timer:0.0
+            trigger_timer:0.0,
+            perception:Perception::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Behaviour {
+    // explores and engages enemies it comes across
+    Balanced,
+    // charges at enemies
+    Aggressive,
+    // stops to shoot from a distance
+    Sniper,
+    // backs away from enemies that come too close
+    Coward
+}
+
+impl Default for Behaviour {
+    fn default() -> Self {
+        Self::Balanced
+    }
+}
+
+// tuning of a bot resolved from its difficulty, personality and map properties
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BotTuning {
+    pub behaviour:Behaviour,
+    // seconds between decisions
+    pub think_interval:f32,
+    // seconds from spotting an enemy until the first shot
+    pub reaction_time:f32,
+    // maximum random error of the aim in radians
+    pub aim_error:f32,
+    // seconds to wait between shots on top of the turret cooldown
+    pub fire_pause:f32,
+    // enemies further away are not fired at
+    pub engagement_range:f32,
+    pub view_range:f32,
+    // distances at which obstacles make the bot turn while exploring
+    pub obstacle_front:f32,
+    pub obstacle_side:f32
+}
+
+impl Default for BotTuning {
+    fn default() -> Self {
+        Self {
+            behaviour:Behaviour::Balanced,
+            think_interval:0.1,
+            reaction_time:1.5,
+            aim_error:0.1,
+            fire_pause:0.5,
+            engagement_range:8.0,
+            view_range:8.0,
+            obstacle_front:1.5,
+            obstacle_side:0.1
         }
     }
-}
\ No newline at end of file
+}
+
+// partial tuning as defined by a preset or map properties, values replace and scales multiply the tuning
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BotPreset {
+    pub behaviour:Option<Behaviour>,
+    pub think_interval:Option<f32>,
+    pub reaction_time:Option<f32>,
+    pub aim_error:Option<f32>,
+    pub fire_pause:Option<f32>,
+    pub engagement_range:Option<f32>,
+    pub view_range:Option<f32>,
+    pub obstacle_front:Option<f32>,
+    pub obstacle_side:Option<f32>,
+    pub reaction_time_scale:Option<f32>,
+    pub aim_error_scale:Option<f32>,
+    pub fire_pause_scale:Option<f32>,
+    pub engagement_range_scale:Option<f32>,
+    pub view_range_scale:Option<f32>
+}
+
+impl BotPreset {
+    // reads overrides from map properties using the same names as the presets, properties which do not parse are logged and skipped
+    pub fn from_properties(properties:&HashMap<String, String>, bot:&str) -> Self {
+        let value = |v:&String| match v.parse::<f64>().ok().and_then(serde_json::Number::from_f64) {
+            Some(n) => serde_json::Value::Number(n),
+            None => serde_json::Value::String(v.to_lowercase())
+        };
+
+        let mut values = serde_json::Map::new();
+        for (k, v) in properties.iter() {
+            let single = serde_json::Value::Object(std::iter::once((k.clone(), value(v))).collect());
+            match serde_json::from_value::<BotPreset>(single) {
+                Ok(_) => { values.insert(k.clone(), value(v)); }
+                Err(err) => warn!("ignoring property '{}' of bot {}: {}", k, bot, err)
+            }
+        }
+
+        serde_json::from_value(serde_json::Value::Object(values)).unwrap_or_default()
+    }
+}
+
+impl BotTuning {
+    pub fn apply(&mut self, preset:&BotPreset) {
+        fn set(value:&mut f32, preset:Option<f32>) {
+            if let Some(v) = preset {
+                *value = v;
+            }
+        }
+        fn scale(value:&mut f32, preset:Option<f32>) {
+            if let Some(v) = preset {
+                *value *= v;
+            }
+        }
+
+        if let Some(behaviour) = preset.behaviour {
+            self.behaviour = behaviour;
+        }
+        set(&mut self.think_interval, preset.think_interval);
+        set(&mut self.reaction_time, preset.reaction_time);
+        set(&mut self.aim_error, preset.aim_error);
+        set(&mut self.fire_pause, preset.fire_pause);
+        set(&mut self.engagement_range, preset.engagement_range);
+        set(&mut self.view_range, preset.view_range);
+        set(&mut self.obstacle_front, preset.obstacle_front);
+        set(&mut self.obstacle_side, preset.obstacle_side);
+        scale(&mut self.reaction_time, preset.reaction_time_scale);
+        scale(&mut self.aim_error, preset.aim_error_scale);
+        scale(&mut self.fire_pause, preset.fire_pause_scale);
+        scale(&mut self.engagement_range, preset.engagement_range_scale);
+        scale(&mut self.view_range, preset.view_range_scale);
+        self.think_interval = self.think_interval.max(0.01);
+    }
+}
+
+// map properties of a bot, resolved into its tuning once the presets are loaded
+#[derive(Debug, Clone, Default)]
+pub struct BotProperties {
+    pub properties:HashMap<String, String>
+}
+
+// marks a bot whose tuning has been resolved from the presets, until then it uses the default tuning
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BotTuned;
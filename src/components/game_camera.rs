@@ -1,4 +1,22 @@
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy)]
 pub struct GameCamera {
+    // zoom factor, 1.0 shows the whole view
+    pub zoom:f32
+}
 
-}
\ No newline at end of file
+impl GameCamera {
+    pub const MIN_ZOOM:f32 = 1.0;
+    pub const MAX_ZOOM:f32 = 4.0;
+
+    pub fn zoom_by(&mut self, factor:f32) {
+        self.zoom = (self.zoom * factor).max(Self::MIN_ZOOM).min(Self::MAX_ZOOM);
+    }
+}
+
+impl Default for GameCamera {
+    fn default() -> Self {
+        Self {
+            zoom:1.0
+        }
+    }
+}
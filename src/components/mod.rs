@@ -35,4 +35,7 @@ mod faction;
 pub use faction::*;
 
 mod autopilot;
-pub use autopilot::*;
\ No newline at end of file
+pub use autopilot::*;
+
+mod unit;
+pub use unit::*;
\ No newline at end of file
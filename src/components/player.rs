@@ -1,5 +1,17 @@
 use serde::{Serialize, Deserialize};
-#[derive(Clone, Copy, Default,Serialize, Deserialize)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Player {
+    // true if the player is controlled by keyboard and mouse
+    pub keyboard_mouse:bool,
+    // id of the gamepad assigned to the player, if any
+    pub gamepad:Option<usize>
+}
 
-}
\ No newline at end of file
+impl Default for Player {
+    fn default() -> Self {
+        Self {
+            keyboard_mouse:true,
+            gamepad:None
+        }
+    }
+}
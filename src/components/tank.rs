@@ -1,12 +1,99 @@
 use bevy::{math::Vec2, prelude::Entity};
 use serde::{Serialize, Deserialize};
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TankClass {
+    Light,
+    Medium,
+    Heavy
+}
+
+impl Default for TankClass {
+    fn default() -> Self {
+        Self::Medium
+    }
+}
+
+impl From<&str> for TankClass {
+    fn from(str: &str) -> Self {
+        match str.to_lowercase().as_str() {
+            "light" => Self::Light,
+            "heavy" => Self::Heavy,
+            _ => Self::Medium
+        }
+    }
+}
+
+impl TankClass {
+    // radians per second the turret of the class can rotate
+    pub fn turret_speed(&self) -> f32 {
+        match self {
+            TankClass::Light => 4.0,
+            TankClass::Medium => 3.0,
+            TankClass::Heavy => 1.75
+        }
+    }
+
+    pub fn track_model(&self) -> TrackModel {
+        match self {
+            TankClass::Light => TrackModel {
+                engine_power:5.0,
+                max_speed:4.0,
+                turn_torque:0.3,
+                track_slip:0.3,
+                track_width:0.8,
+                density:0.6
+            },
+            TankClass::Medium => TrackModel::default(),
+            TankClass::Heavy => TrackModel {
+                engine_power:12.0,
+                max_speed:2.0,
+                turn_torque:0.8,
+                track_slip:0.1,
+                track_width:0.9,
+                density:2.0
+            }
+        }
+    }
+}
+
+// differential drive model, where each track pushes the tank at its own side
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct TrackModel {
+    // force of a single track at full throttle
+    pub engine_power:f32,
+    // speed at which the tracks no longer produce any force
+    pub max_speed:f32,
+    // additional torque helping the tank turn on the spot
+    pub turn_torque:f32,
+    // 0.0 means the tracks fully grip and resist turning, 1.0 means the tank spins freely
+    pub track_slip:f32,
+    // distance between the center of the tracks
+    pub track_width:f32,
+    // density of the tank collider, which determines mass and inertia
+    pub density:f32
+}
+
+impl Default for TrackModel {
+    fn default() -> Self {
+        Self {
+            engine_power:6.5,
+            max_speed:3.0,
+            turn_torque:0.4,
+            track_slip:0.2,
+            track_width:0.8,
+            density:1.0
+        }
+    }
+}
 
 #[derive(Copy, Clone, Serialize, Deserialize)]
 pub struct Tank {
+    // throttle of the right and left track, in the range -1.0 to 1.0
     pub tracks:Vec2,
     pub turret_entity:Entity,
-    pub tracks_distance:Vec2
+    pub tracks_distance:Vec2,
+    pub model:TrackModel
 }
 
 impl Tank {
@@ -14,7 +101,13 @@ impl Tank {
         Self {
             tracks:Vec2::default(),
             turret_entity,
-            tracks_distance:Vec2::default()
+            tracks_distance:Vec2::default(),
+            model:TrackModel::default()
         }
     }
-}
\ No newline at end of file
+
+    pub fn with_model(mut self, model:TrackModel) -> Self {
+        self.model = model;
+        self
+    }
+}
@@ -1,11 +1,49 @@
 use serde::{Serialize, Deserialize};
 use bevy::math::Vec3;
 
+// what a turret fires, levels can restrict the weapons of the player by name
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Weapon {
+    Cannon
+}
+
+impl Weapon {
+    pub fn all() -> Vec<Weapon> {
+        vec![Weapon::Cannon]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Weapon::Cannon => "cannon"
+        }
+    }
+
+    pub fn from_name(name:&str) -> Option<Weapon> {
+        Self::all().into_iter().find(|w| w.name() == name.trim().to_lowercase())
+    }
+}
+
 #[derive(Copy, Clone, Serialize, Deserialize)]
 pub struct  Turret {
     pub target:Vec3,
     pub cooldown:f32,
-    pub trigger:bool
+    pub trigger:bool,
+    // radians per second the turret can rotate
+    pub max_angular_speed:f32,
+    // seconds between shots
+    pub reload_time:f32,
+    // unarmed turrets still aim but never fire
+    pub weapon:Option<Weapon>,
+    // optional min and max angle relative to the hull, in radians
+    pub traverse:Option<(f32, f32)>,
+    // the turret only fires when it points this close to the target
+    pub aim_tolerance:f32,
+    // distance from the center of the tank to the end of the barrel, beyond the corners of the hull
+    pub muzzle_offset:f32,
+    // current angle relative to the hull
+    pub angle:f32,
+    // true if the turret points at the target within the aim tolerance
+    pub aligned:bool
 }
 
 impl Default for Turret {
@@ -13,7 +51,32 @@ impl Default for Turret {
         Self {
             target:Vec3::default(),
             cooldown:0.0,
-            trigger:false
+            trigger:false,
+            max_angular_speed:3.0,
+            reload_time:1.0,
+            weapon:Some(Weapon::Cannon),
+            traverse:None,
+            aim_tolerance:0.1,
+            muzzle_offset:0.75,
+            angle:0.0,
+            aligned:false
         }
     }
-}
\ No newline at end of file
+}
+
+impl Turret {
+    pub fn with_max_angular_speed(mut self, max_angular_speed:f32) -> Self {
+        self.max_angular_speed = max_angular_speed;
+        self
+    }
+
+    pub fn with_reload_time(mut self, reload_time:f32) -> Self {
+        self.reload_time = reload_time;
+        self
+    }
+
+    pub fn with_traverse(mut self, traverse:Option<(f32, f32)>) -> Self {
+        self.traverse = traverse;
+        self
+    }
+}
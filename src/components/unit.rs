@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Order {
+    // no orders, bots fall back to their own behaviour
+    Idle,
+    Move(Vec3),
+    // move, but stop to engage enemies on the way
+    AttackMove(Vec3),
+    Hold
+}
+
+impl Default for Order {
+    fn default() -> Self {
+        Self::Idle
+    }
+}
+
+// friendly tank which can be commanded in rts mode
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Unit {
+    pub order:Order
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Selected;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Formation {
+    Line,
+    Column,
+    Wedge,
+    Box
+}
+
+impl Default for Formation {
+    fn default() -> Self {
+        Self::Wedge
+    }
+}
+
+impl Formation {
+    pub fn next(&self) -> Self {
+        match self {
+            Formation::Line => Formation::Column,
+            Formation::Column => Formation::Wedge,
+            Formation::Wedge => Formation::Box,
+            Formation::Box => Formation::Line
+        }
+    }
+
+    // offset of slot i, where x points towards the target and y to the left
+    pub fn offset(&self, i:usize, count:usize, spacing:f32) -> Vec2 {
+        let i = i as f32;
+        let count = count as f32;
+        match self {
+            Formation::Line => Vec2::new(0.0, (i - (count - 1.0) / 2.0) * spacing),
+            Formation::Column => Vec2::new(-i * spacing, 0.0),
+            Formation::Wedge => {
+                // leader at the tip, the others alternate left and right behind it
+                let rank = ((i + 1.0) / 2.0).floor();
+                let side = if i as usize % 2 == 1 { 1.0 } else { -1.0 };
+                Vec2::new(-rank * spacing, rank * side * spacing)
+            }
+            Formation::Box => {
+                let columns = count.sqrt().ceil().max(1.0);
+                let row = (i / columns).floor();
+                let column = i % columns;
+                Vec2::new(-row * spacing, (column - (columns - 1.0) / 2.0) * spacing)
+            }
+        }
+    }
+}
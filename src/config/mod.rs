@@ -1,16 +1,55 @@
-use extensions::Ini;
+use bevy::log::warn;
+use extensions::{AudioMixer, Ini};
+
+// user overrides are kept apart, such that saving them never rewrites the documented config file
+const USER_FILE:&str = "user.ini";
 
 pub struct Config {
-    pub ini:Ini
+    // the config file with the user overrides layered over it
+    pub ini:Ini,
+    pub path:String,
+    // only the values changed in game, written to user.ini
+    user:Ini
 }
 
 impl Config {
     pub fn new(file:&str) -> Self{
+        let mut ini = Ini::load_from_file(file).unwrap_or_default();
+        let user = Ini::load_from_file(USER_FILE).unwrap_or_default();
+        for (section, properties) in user.iter() {
+            for (key, value) in properties.iter() {
+                ini.with_section(section).set(key, value);
+            }
+        }
+
         Self {
-            ini:Ini::load_from_file(file).unwrap_or_default()
+            ini,
+            path:file.into(),
+            user
+        }
+    }
+
+    // writes the values changed in game to user.ini
+    pub fn save(&self) {
+        if let Err(err) = self.user.write_to_file(USER_FILE) {
+            warn!("could not save config to {}: {}", USER_FILE, err);
         }
     }
 
+    pub fn binding(&self, action:&str) -> Option<&str> {
+        self.ini.get_from(Some("bindings"), action)
+    }
+
+    pub fn set_binding(&mut self, action:&str, value:&str) {
+        self.ini.with_section(Some("bindings")).set(action, value);
+        self.user.with_section(Some("bindings")).set(action, value);
+    }
+
+    pub fn set_value(&mut self, key:&str, value:&str) {
+        self.ini.with_section(None::<String>).set(key, value);
+        self.user.with_section(None::<String>).set(key, value);
+    }
+
     fn key_bool_with_default(&self, key:&str, default:bool) -> bool {
         self.ini.get_from::<&str>(None, key).unwrap_or(if default {"true"} else {"false"}) == "true"
     }
@@ -24,6 +63,19 @@ impl Config {
         v.parse::<u32>().unwrap_or(default)
     }
 
+    fn key_f32_with_default(&self, key:&str, default:f32) -> f32 {
+        let v = self.ini.get_from::<&str>(None, key).unwrap_or("");
+        if v == "" {
+            return default;
+        }
+
+        v.parse::<f32>().unwrap_or(default)
+    }
+
+    fn key_str_with_default<'a>(&'a self, key:&str, default:&'a str) -> &'a str {
+        self.ini.get_from::<&str>(None, key).unwrap_or(default)
+    }
+
     pub fn debug(&self) -> bool {
         self.key_bool_with_default("debug", false)
     }
@@ -51,4 +103,52 @@ impl Config {
     pub fn quick(&self) -> bool {
         self.key_bool_with_default("quick", false)
     }
+
+    // reload maps, levels, bot presets and sounds when their files change, native builds only
+    pub fn hot_reload(&self) -> bool {
+        self.key_bool_with_default("hot_reload", false)
+    }
+
+    // stop the game as soon as an asset fails to load instead of reporting it on the splash screen
+    pub fn fail_fast(&self) -> bool {
+        self.key_bool_with_default("fail_fast", false)
+    }
+
+    pub fn gamepad_deadzone(&self) -> f32 {
+        self.key_f32_with_default("gamepad_deadzone", 0.2)
+    }
+
+    // either 'arcade', where the left stick points in the direction to drive, or 'tank', where it controls throttle and turning
+    pub fn gamepad_drive_mode(&self) -> &str {
+        self.key_str_with_default("gamepad_drive_mode", "arcade")
+    }
+
+    // distance between waypoints when drawing an autopilot path
+    pub fn waypoint_spacing(&self) -> f32 {
+        self.key_f32_with_default("waypoint_spacing", 0.5).max(0.1)
+    }
+
+    // difficulty preset of the bots, as defined in bots.json
+    pub fn difficulty(&self) -> &str {
+        self.key_str_with_default("difficulty", "normal")
+    }
+
+    // volumes of the audio channels, between 0.0 and 1.0
+    pub fn audio_mixer(&self) -> AudioMixer {
+        let volume = |key:&str, default:f32| self.key_f32_with_default(key, default).max(0.0).min(1.0);
+        AudioMixer {
+            master:volume("master_volume", 1.0),
+            music:volume("music_volume", 0.5),
+            sfx:volume("sfx_volume", 1.0),
+            ui:volume("ui_volume", 1.0),
+            ..Default::default()
+        }
+    }
+
+    pub fn set_audio_mixer(&mut self, mixer:&AudioMixer) {
+        self.set_value("master_volume", &mixer.master.to_string());
+        self.set_value("music_volume", &mixer.music.to_string());
+        self.set_value("sfx_volume", &mixer.sfx.to_string());
+        self.set_value("ui_volume", &mixer.ui.to_string());
+    }
 }
\ No newline at end of file
@@ -2,6 +2,7 @@
 #[derive(Debug)]
 pub enum ConsoleCommand {
     LoadMap(String),
+    GenerateMap(usize, u64),
     SaveState(u8),
     LoadState(u8)
 }
\ No newline at end of file
@@ -5,7 +5,7 @@ use bevy::prelude::*;
 mod console_command;
 pub use console_command::*;
 
-use crate::{MapLoader, Persister, PersisterCommand};
+use crate::{AssetCache, AssetManifest, MapLoader, Persister, PersisterCommand};
 
 #[derive(Default)]
 pub struct Console {
@@ -30,6 +30,9 @@ impl Console {
     pub fn load_map(&mut self, map_name:&str) {
         self.push_command(ConsoleCommand::LoadMap(map_name.into()));
     }
+    pub fn generate_map(&mut self, size:usize, seed:u64) {
+        self.push_command(ConsoleCommand::GenerateMap(size, seed));
+    }
     pub fn load_state(&mut self, index:u8) {
         self.push_command(ConsoleCommand::LoadState(index));
     }
@@ -38,13 +41,18 @@ impl Console {
     }
 }
 
-pub fn command_interpreter(mut persister:ResMut<Persister>, mut console:ResMut<Console>, asset_server:Res<AssetServer>, mut map_loader:ResMut<MapLoader>) {
+pub fn command_interpreter(mut persister:ResMut<Persister>, mut console:ResMut<Console>, asset_server:Res<AssetServer>, mut map_loader:ResMut<MapLoader>, manifest:Res<AssetManifest>, mut asset_cache:ResMut<AssetCache>) {
     if let Some(command) = console.pop_command() {
         match command {
             ConsoleCommand::LoadMap(map_name) => {
                 let path:String = "maps/".to_owned() + &map_name + ".tmx";
+                // only the assets referenced by the map are loaded with it
+                manifest.preload(&path, &mut asset_cache, &asset_server);
                 map_loader.load_map(&path, asset_server);
             }
+            ConsoleCommand::GenerateMap(size, seed) => {
+                map_loader.generate_map(size, seed);
+            }
             ConsoleCommand::SaveState(_index) => {
                 persister.push_command(PersisterCommand::SaveState);
             },
@@ -0,0 +1,74 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContext, egui::{self, Align2}};
+
+use crate::{AppState, Config, DelayState, Hud, Profile};
+
+use super::Director;
+
+pub(super) fn format_time(secs:f32) -> String {
+    format!("{}:{:02}", secs as u32 / 60, secs as u32 % 60)
+}
+
+pub(super) fn enter_level_select(mut hud:ResMut<Hud>) {
+    hud.clear_texts();
+}
+
+pub(super) fn level_select_ui(egui_context:Res<EguiContext>,
+    mut director:ResMut<Director>,
+    profile:Res<Profile>,
+    config:Res<Config>,
+    mut app_state:ResMut<DelayState<AppState>>,
+    mut hud:ResMut<Hud>) {
+    if app_state.has_state() {
+        return;
+    }
+
+    let mut selected = None;
+    let mut back = false;
+    egui::Window::new("Select Level")
+    .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+    .collapsible(false)
+    .resizable(false)
+    .show(egui_context.ctx(), |ui| {
+        if director.ready == false {
+            ui.label("Loading levels...");
+            return;
+        }
+
+        egui::Grid::new("Levels").striped(true).show(ui, |ui| {
+            for i in 1..=director.levels.count() {
+                let level = director.levels.get(i).unwrap();
+                let progress = profile.progress(&level.map).cloned().unwrap_or_default();
+                let unlocked = profile.is_unlocked(&director.levels, i);
+
+                ui.label(i.to_string());
+                ui.label(level.display_name(i));
+                ui.label(progress.best_time.map(format_time).unwrap_or_else(|| "-".into()));
+                ui.label(format!("{}{}", "*".repeat(progress.stars as usize), "-".repeat(3 - progress.stars.min(3) as usize)));
+                if ui.add(egui::Button::new(if unlocked { "Play" } else { "Locked" }).enabled(unlocked)).clicked() {
+                    selected = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+
+        ui.separator();
+        if ui.button("Back").clicked() {
+            back = true;
+        }
+    });
+
+    if back {
+        app_state.set(AppState::MainMenu, 0.0);
+        return;
+    }
+
+    if let Some(level) = selected {
+        director.start_level(level);
+        let time = if config.quick() == false { 0.5 } else { 0.0 };
+        app_state.set(AppState::InGame, time);
+        if config.quick() == false {
+            hud.fade(time, time, Color::BLACK);
+        }
+    }
+}
@@ -1,14 +1,185 @@
-use serde::{Serialize, Deserialize};
+use std::{convert::TryFrom, fmt};
+
+use extensions::Playlist;
+use serde::{Serialize, Deserialize, de::{self, MapAccess, Visitor}};
+
+use crate::Weapon;
+
+// a level of the campaign, written in levels.json either as just the map name or as an object
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Level {
+    // name of the map in the maps folder, without extension
+    pub map:String,
+    // shown when the level starts, defaults to "Level N"
+    pub name:Option<String>,
+    pub briefing:String,
+    // falls back to the music of the campaign if none
+    pub music:Option<Playlist>,
+    // seconds to win the level in, unlimited if none
+    pub time_limit:Option<f32>,
+    // seconds in which the level should be won to beat par
+    pub par_time:Option<f32>,
+    // names of the weapons the player may use, all if empty
+    pub weapons:Vec<String>,
+    // levels, counted from 1, that must be won first, defaults to the previous level
+    pub requires:Option<Vec<u32>>
+}
+
+impl Level {
+    pub fn display_name(&self, level:u32) -> String {
+        self.name.clone().unwrap_or_else(|| format!("Level {}", level))
+    }
+
+    pub fn allows(&self, weapon:Weapon) -> bool {
+        self.weapons.len() == 0 || self.weapons.iter().any(|w| Weapon::from_name(w) == Some(weapon))
+    }
+}
+
+enum LevelEntry {
+    Map(String),
+    Level(Level)
+}
+
+// a visitor instead of an untagged enum, such that errors in a level object are reported as is
+struct LevelEntryVisitor;
+
+impl<'de> Visitor<'de> for LevelEntryVisitor {
+    type Value = LevelEntry;
+
+    fn expecting(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map name or a level object")
+    }
+
+    fn visit_str<E:de::Error>(self, v:&str) -> Result<Self::Value, E> {
+        Ok(LevelEntry::Map(v.into()))
+    }
+
+    fn visit_map<A:MapAccess<'de>>(self, map:A) -> Result<Self::Value, A::Error> {
+        Level::deserialize(de::value::MapAccessDeserializer::new(map)).map(LevelEntry::Level)
+    }
+}
+
+impl<'de> Deserialize<'de> for LevelEntry {
+    fn deserialize<D:serde::Deserializer<'de>>(deserializer:D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(LevelEntryVisitor)
+    }
+}
 
 #[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LevelsFile {
+    #[serde(default)]
+    music:Playlist,
+    #[serde(rename = "maps")]
+    levels:Vec<LevelEntry>
+}
+
+impl Serialize for LevelEntry {
+    fn serialize<S:serde::Serializer>(&self, serializer:S) -> Result<S::Ok, S::Error> {
+        match self {
+            LevelEntry::Map(map) => map.serialize(serializer),
+            LevelEntry::Level(level) => level.serialize(serializer)
+        }
+    }
+}
+
+// the campaign, validated when deserialized
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "LevelsFile", into = "LevelsFile")]
 pub struct Levels {
-    pub maps:Vec<String>
+    pub levels:Vec<Level>,
+    pub music:Playlist
+}
+
+impl TryFrom<LevelsFile> for Levels {
+    type Error = String;
+
+    fn try_from(file:LevelsFile) -> Result<Self, Self::Error> {
+        if file.levels.len() == 0 {
+            return Err("the campaign has no levels".into());
+        }
+
+        let count = file.levels.len() as u32;
+        let mut levels = Vec::new();
+        for (i, entry) in file.levels.into_iter().enumerate() {
+            let number = i as u32 + 1;
+            let mut level = match entry {
+                LevelEntry::Map(map) => Level { map, ..Default::default() },
+                LevelEntry::Level(level) => level
+            };
+
+            if level.map.trim().len() == 0 {
+                return Err(format!("level {} has no map", number));
+            }
+            if let Some(time_limit) = level.time_limit {
+                if time_limit <= 0.0 {
+                    return Err(format!("level {} has a time_limit of {}, it must be positive", number, time_limit));
+                }
+            }
+            if let Some(par_time) = level.par_time {
+                if par_time <= 0.0 {
+                    return Err(format!("level {} has a par_time of {}, it must be positive", number, par_time));
+                }
+                if par_time > level.time_limit.unwrap_or(f32::MAX) {
+                    return Err(format!("level {} has a par_time longer than its time_limit", number));
+                }
+            }
+            if let Some(weapon) = level.weapons.iter().find(|w| Weapon::from_name(w).is_none()) {
+                let known:Vec<&str> = Weapon::all().iter().map(|w| w.name()).collect();
+                return Err(format!("level {} allows an unknown weapon '{}', known weapons are {}", number, weapon, known.join(", ")));
+            }
+
+            let requires = level.requires.get_or_insert_with(|| if number > 1 { vec![number - 1] } else { Vec::new() });
+            if let Some(required) = requires.iter().find(|r| **r == 0 || **r > count || **r == number) {
+                return Err(format!("level {} requires level {} which is not another level of the campaign", number, required));
+            }
+
+            levels.push(level);
+        }
+
+        // a cycle in the requirements would lock its levels for good
+        for start in 1..=count {
+            let mut stack = vec![start];
+            let mut visited = vec![false; count as usize + 1];
+            while let Some(number) = stack.pop() {
+                for required in levels[number as usize - 1].requires.iter().flatten() {
+                    if *required == start {
+                        return Err(format!("level {} requires itself through level {}", start, number));
+                    }
+                    if visited[*required as usize] == false {
+                        visited[*required as usize] = true;
+                        stack.push(*required);
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            levels,
+            music:file.music
+        })
+    }
+}
+
+impl From<Levels> for LevelsFile {
+    fn from(levels:Levels) -> Self {
+        Self {
+            music:levels.music,
+            levels:levels.levels.into_iter().map(LevelEntry::Level).collect()
+        }
+    }
 }
 
 impl Default for Levels {
     fn default() -> Self {
         Self {
-            maps:vec!["1".into()]
+            levels:vec![Level {
+                map:"1".into(),
+                requires:Some(Vec::new()),
+                ..Default::default()
+            }],
+            music:Playlist::default()
         }
     }
 
@@ -16,14 +187,23 @@ impl Default for Levels {
 
 impl Levels {
     pub fn count(&self) -> u32 {
-        self.maps.len() as u32
+        self.levels.len() as u32
     }
 
-    pub fn get_map(&self, level:u32) -> String {
-        if let Some(map) = self.maps.get((level -1) as usize) {
-            return map.clone();
+    // levels are counted from 1
+    pub fn get(&self, level:u32) -> Option<&Level> {
+        level.checked_sub(1).and_then(|i| self.levels.get(i as usize))
+    }
+
+    pub fn get_map(&self, level:u32) -> Option<&str> {
+        self.get(level).map(|l| l.map.as_str())
+    }
+
+    pub fn get_playlist(&self, level:u32) -> Playlist {
+        if let Some(Some(playlist)) = self.get(level).map(|l| &l.music) {
+            return playlist.clone();
         }
 
-        return Default::default();
-    } 
-}
\ No newline at end of file
+        self.music.clone()
+    }
+}
@@ -1,10 +1,13 @@
 
 use bevy::{prelude::*};
-use crate::{AppState, AssetCache, Bot, Config, Console, GameState, Hud, Json, PlayAudioEvent, Player};
+use crate::{Action, Actions, AppState, AssetCache, AudioBus, Bot, Config, Console, DelayState, Faction, GameState, Health, Hud, Json, Level, LevelProgress, LevelStats, MapReloadedEvent, MusicDirector, MusicEvent, NewGameEvent, PlayAudioEvent, Player, Profile, Stats, Tank, Turret, Weapon};
 
 mod levels;
 pub use levels::*;
 
+mod level_select;
+use level_select::*;
+
 enum DirectorState {
     StartLoadLevel,
     LoadLevel,
@@ -12,22 +15,32 @@ enum DirectorState {
     InProgress,
     Go,
     Died,
+    OutOfTime,
     WonLevel,
+    AwaitNextLevelInput,
     WonGame,
     AwaitRestartGameInput
 }
 
-struct Director {
+pub struct Director {
     pub ready:bool,
     pub quick:bool,
     pub current_level:u32,
     pub levels:Levels, 
     pub timer:f32,
-    pub state:DirectorState
+    // seconds the current level has been in progress
+    pub level_time:f32,
+    state:DirectorState,
+    // map size and seed of the random skirmish being played, if any
+    pub skirmish:Option<(usize, u64)>,
+    // true until the first level of a new game has been loaded
+    pub new_game:bool,
+    // stats of the level last won
+    pub summary:String
 }
 
 impl Director {
-    pub fn transition(&mut self, new_state:DirectorState, secs:f32) {
+    fn transition(&mut self, new_state:DirectorState, secs:f32) {
         self.timer = secs;
         if self.quick {
             self.timer = 0.25;
@@ -37,6 +50,21 @@ impl Director {
 
     pub fn reset(&mut self) {
         self.current_level = 1;
+        self.skirmish = None;
+        self.new_game = true;
+    }
+
+    // plays the current level or skirmish again
+    pub fn restart(&mut self) {
+        self.transition(DirectorState::StartLoadLevel, 0.0);
+    }
+
+    // plays the level once the game is shown
+    pub fn start_level(&mut self, level:u32) {
+        self.skirmish = None;
+        self.current_level = level;
+        self.new_game = true;
+        self.transition(DirectorState::StartLoadLevel, 0.0);
     }
 }
 
@@ -48,26 +76,85 @@ impl Default for Director {
             current_level:1,
             levels:Levels::default(),
             timer:0.0,
-            state:DirectorState::LoadLevel
+            level_time:0.0,
+            state:DirectorState::LoadLevel,
+            skirmish:None,
+            new_game:true,
+            summary:String::new()
         }
     }
 }
 
+// one star for winning, one for beating the par time and one for keeping at least half of the health
+fn stars(level:&Level, time:f32, health:f32) -> u8 {
+    let par = level.par_time.map(|p| time <= p).unwrap_or(false);
+    let healthy = health >= Health::default().amount / 2.0;
+    1 + par as u8 + healthy as u8
+}
+
+// stats of a won level, marking personal bests if the level had been won before
+fn summary(stats:&LevelStats, previous:Option<&LevelProgress>) -> String {
+    let previous = previous.filter(|p| p.completed);
+    let best = |new:bool| if new && previous.is_some() { " - new best!" } else { "" };
+    let p = previous.cloned().unwrap_or_default();
+    format!("Time {}{}\nAccuracy {:.0}% ({} of {} shots){}\nKills {}{}\nDamage taken {:.0}{}",
+        format_time(stats.time), best(p.best_time.map(|t| stats.time < t).unwrap_or(false)),
+        stats.accuracy() * 100.0, stats.hits, stats.shots, best(p.best_accuracy.map(|a| stats.accuracy() > a).unwrap_or(false)),
+        stats.kills, best(stats.kills > p.most_kills),
+        stats.damage_taken, best(p.least_damage.map(|d| stats.damage_taken < d).unwrap_or(false)))
+}
+
 fn update(
     mut director:ResMut<Director>, 
-    players:Query<&Player>, 
-    bots:Query<&Bot>, 
+    players:Query<Option<&Health>, With<Player>>, 
+    mut profile:ResMut<Profile>,
+    mut stats:ResMut<Stats>,
+    mut app_state:ResMut<DelayState<AppState>>,
+    bots:Query<&Faction, With<Bot>>, 
     time:Res<Time>, 
     mut game_state:ResMut<State<GameState>>,
     mut console:ResMut<Console>,
     mut hud:ResMut<Hud>,
-    mouse_button_input:Res<Input<MouseButton>>,
-    mut play_audio:EventWriter<PlayAudioEvent>) {
+    actions:Res<Actions>,
+    mut play_audio:EventWriter<PlayAudioEvent>,
+    mut music:EventWriter<MusicEvent>,
+    mut new_game_reader:EventReader<NewGameEvent>,
+    mut map_reloaded_reader:EventReader<MapReloadedEvent>) {
 
     if director.ready == false {
         return;
     }
 
+    for e in new_game_reader.iter() {
+        music.send(MusicEvent::Stop);
+        let _ = game_state.overwrite_set(GameState::Paused);
+        director.skirmish = Some((e.map_size, e.seed));
+        director.new_game = true;
+        director.transition(DirectorState::StartLoadLevel, 0.0);
+    }
+
+    // a map changed on disk restarts the countdown of the level being played
+    for _ in map_reloaded_reader.iter() {
+        if let DirectorState::Go | DirectorState::InProgress = director.state {
+            let _ = game_state.overwrite_set(GameState::Paused);
+            director.transition(DirectorState::GetReady, 0.0);
+        }
+    }
+
+    if let DirectorState::InProgress = director.state {
+        director.level_time += time.delta_seconds();
+    }
+
+    // time left of the level being played, if it has a time limit
+    let time_left = match (director.skirmish, director.levels.get(director.current_level).and_then(|l| l.time_limit)) {
+        (None, Some(time_limit)) => Some((time_limit - director.level_time).max(0.0)),
+        _ => None
+    };
+    if let (DirectorState::InProgress, Some(time_left)) = (&director.state, time_left) {
+        let level = director.current_level;
+        hud.top_left_text = format!("Level {} of {}\nTime left {}:{:02}", level, director.levels.count(), time_left as u32 / 60, time_left as u32 % 60);
+    }
+
     if director.timer > 0.0 {
         director.timer -= time.delta_seconds();
         return;
@@ -75,7 +162,8 @@ fn update(
     director.timer = 0.0;
 
     let is_player_alive = players.iter().len() > 0;
-    let some_enemies_left = bots.iter().len() > 0;
+    // allied bots fight on the side of the player
+    let some_enemies_left = bots.iter().any(|f| *f == Faction::Reds);
 
 
     match director.state {
@@ -88,28 +176,69 @@ fn update(
         }
         DirectorState::LoadLevel => {
             hud.clear_texts();
-            console.load_map(&director.levels.get_map(director.current_level));
+            director.level_time = 0.0;
+            if director.new_game {
+                director.new_game = false;
+                stats.reset();
+            }
+            stats.start_level();
+            if let Some((size, seed)) = director.skirmish {
+                console.generate_map(size, seed);
+            } else if let Some(map) = director.levels.get_map(director.current_level).map(|m| m.to_string()) {
+                console.load_map(&map);
+            } else {
+                // the campaign changed, start it over
+                console.logln(format!("level {} does not exist", director.current_level));
+                director.reset();
+                director.transition(DirectorState::LoadLevel, 0.0);
+                return;
+            }
             director.transition(DirectorState::GetReady, 0.0);
         },
         DirectorState::GetReady => {
-            play_audio.send("sfx/get_ready.ogg".into());
+            play_audio.send(PlayAudioEvent::new("announcer.get_ready").with_bus(AudioBus::Ui));
             hud.center_text = "Get Ready!!!".into();
-            hud.top_left_text = "Level ".to_string() + director.current_level.to_string().as_str() + " of " + &director.levels.count().to_string();
+            if director.skirmish.is_some() {
+                hud.top_left_text = "Random Skirmish".into();
+            } else {
+                hud.top_left_text = "Level ".to_string() + director.current_level.to_string().as_str() + " of " + &director.levels.count().to_string();
+                if let Some(level) = director.levels.get(director.current_level) {
+                    hud.center_text = format!("{}\n{}\n\nGet Ready!!!", level.display_name(director.current_level), level.briefing);
+                }
+            }
             director.transition(DirectorState::Go, 1.5);
         },
         DirectorState::Go => {
-            play_audio.send("sfx/go.ogg".into());
+            play_audio.send(PlayAudioEvent::new("announcer.go").with_bus(AudioBus::Ui));
             hud.center_text = "Go!!!".into();
             let _ = game_state.overwrite_set(GameState::Running);
 
             director.transition(DirectorState::InProgress, 1.0);
-            play_audio.send(PlayAudioEvent::new("music/Zander Noriega - Fight Them Until We Cant.ogg").with_music(true));
+            let playlist = if director.skirmish.is_some() { director.levels.music.clone() } else { director.levels.get_playlist(director.current_level) };
+            music.send(MusicEvent::Play(playlist));
         },
         DirectorState::InProgress => {
             if is_player_alive == false {
                 director.transition(DirectorState::Died, 1.0);
+            } else if time_left == Some(0.0) {
+                director.transition(DirectorState::OutOfTime, 0.0);
             } else if some_enemies_left == false {
-                if director.current_level == director.levels.count() {
+                let time = director.level_time;
+                stats.finish_level(time);
+                let mut previous = None;
+                if director.skirmish.is_none() {
+                    // save the progress right away, such that quitting now keeps it
+                    let health = players.iter().next().flatten().map(|h| h.amount).unwrap_or(0.0);
+                    if let Some(level) = director.levels.get(director.current_level) {
+                        let stars = stars(level, time, health);
+                        let map = level.map.clone();
+                        previous = Some(profile.complete(&map, &stats.level, stars));
+                        profile.save();
+                    }
+                }
+                director.summary = summary(&stats.level, previous.as_ref());
+
+                if director.skirmish.is_some() || director.current_level == director.levels.count() {
                     director.transition(DirectorState::WonGame, 1.0);
                 } else {
                     director.transition(DirectorState::WonLevel, 1.0);
@@ -118,44 +247,110 @@ fn update(
             hud.center_text = "".into();
         },
         DirectorState::Died => {
-            play_audio.send(PlayAudioEvent::new("").with_music(true));
-            play_audio.send("sfx/too_bad.ogg".into());
+            music.send(MusicEvent::Stop);
+            play_audio.send(PlayAudioEvent::new("announcer.too_bad").with_bus(AudioBus::Ui));
             hud.center_text = "You died! Restarting level...".into();
             let _ = game_state.overwrite_set(GameState::Paused);
             director.transition(DirectorState::StartLoadLevel, 1.0);
         },
-        DirectorState::WonLevel => {
-            play_audio.send(PlayAudioEvent::new("").with_music(true));
-            play_audio.send("sfx/great.ogg".into());
-            hud.center_text = "All Enemies are dead!\nStarting next level...".into();
+        DirectorState::OutOfTime => {
+            music.send(MusicEvent::Stop);
+            play_audio.send(PlayAudioEvent::new("announcer.too_bad").with_bus(AudioBus::Ui));
+            hud.center_text = "Out of time! Restarting level...".into();
             let _ = game_state.overwrite_set(GameState::Paused);
-            director.current_level += 1;
             director.transition(DirectorState::StartLoadLevel, 1.0);
         },
+        DirectorState::WonLevel => {
+            music.send(MusicEvent::Stop);
+            play_audio.send(PlayAudioEvent::new("announcer.great").with_bus(AudioBus::Ui));
+            hud.center_text = format!("All Enemies are dead!\n\n{}\n\nClick to start the next level...", director.summary);
+            let _ = game_state.overwrite_set(GameState::Paused);
+            director.transition(DirectorState::AwaitNextLevelInput, 0.5);
+        },
+        DirectorState::AwaitNextLevelInput => {
+            if actions.just_pressed(Action::Confirm) {
+                director.current_level += 1;
+                director.transition(DirectorState::StartLoadLevel, 0.0);
+            }
+        },
         DirectorState::WonGame => {
-            play_audio.send(PlayAudioEvent::new("").with_music(true));
-            play_audio.send("sfx/won.ogg".into());
-            hud.center_text = "You Won the Game!\nCongratulations!\nClick to restart the game...".into();
+            music.send(MusicEvent::Stop);
+            play_audio.send(PlayAudioEvent::new("announcer.won").with_bus(AudioBus::Ui));
+            if director.skirmish.is_some() {
+                hud.center_text = format!("You Won the Skirmish!\n\n{}\n\nClick to return to the level select...", director.summary);
+            } else {
+                let game = stats.game;
+                hud.center_text = format!("You Won the Game!\nCongratulations!\n\n{}\n\nAll levels: {}, accuracy {:.0}%, {} kills\n\nClick to return to the level select...",
+                    director.summary, format_time(game.time), game.accuracy() * 100.0, game.kills);
+            }
             let _ = game_state.overwrite_set(GameState::Paused);
             director.transition(DirectorState::AwaitRestartGameInput, 0.5);
         },
         DirectorState::AwaitRestartGameInput => {
-            if mouse_button_input.just_pressed(MouseButton::Left) {
-                // reset game
+            if actions.just_pressed(Action::Confirm) && app_state.has_state() == false {
+                // back to the level select, which starts the next game
                 director.reset();
-                director.transition(DirectorState::StartLoadLevel, 0.0);
+                hud.clear_texts();
+                let time = if director.quick == false { 0.5 } else { 0.0 };
+                app_state.set(AppState::LevelSelect, time);
+                if director.quick == false {
+                    hud.fade(time, time, Color::BLACK);
+                }
             }
         },
     }
     
 }
 
+// seconds the intense music keeps playing after the fighting stopped
+const COMBAT_MUSIC_LINGER:f32 = 6.0;
+
+// switches to intense music while enemies see the player or the player is badly hurt
+fn music_intensity(music_director:Res<MusicDirector>,
+    mut music:EventWriter<MusicEvent>,
+    bots:Query<(&Bot, &Faction)>,
+    players:Query<&Health, With<Player>>,
+    time:Res<Time>,
+    mut linger:Local<f32>) {
+    let spotted = bots.iter().any(|(bot, faction)| *faction == Faction::Reds && bot.sensors.visible_enemies.len() > 0);
+    let hurt = players.iter().any(|health| health.amount < Health::default().amount / 2.0);
+    if spotted || hurt {
+        *linger = COMBAT_MUSIC_LINGER;
+    } else {
+        *linger = (*linger - time.delta_seconds()).max(0.0);
+    }
+
+    let intense = *linger > 0.0;
+    if intense != music_director.is_intense() {
+        music.send(MusicEvent::Intensity(intense));
+    }
+}
+
+// arms the turret of the player with the first weapon the level allows, or none at all
+fn arm_players(director:Res<Director>, players:Query<&Tank, (With<Player>, Added<Tank>)>, mut turrets:Query<&mut Turret>) {
+    let level = if director.skirmish.is_some() { None } else { director.levels.get(director.current_level) };
+    for tank in players.iter() {
+        if let Ok(mut turret) = turrets.get_mut(tank.turret_entity) {
+            turret.weapon = Weapon::all().into_iter().find(|w| level.map(|l| l.allows(*w)).unwrap_or(true));
+        }
+    }
+}
+
 fn startup(mut director:ResMut<Director>, config:Res<Config>) {
     director.quick = config.quick();
 }
 
-fn load_director(mut director:ResMut<Director>, asset_server:Res<AssetServer>, json:Res<Assets<Json>>, mut asset_cache:ResMut<AssetCache>) {
-    
+fn load_director(mut director:ResMut<Director>, asset_server:Res<AssetServer>, json:Res<Assets<Json>>, mut asset_cache:ResMut<AssetCache>, mut events:EventReader<AssetEvent<Json>>, mut console:ResMut<Console>) {
+    // levels.json changed on disk, read it again
+    for e in events.iter() {
+        if let AssetEvent::Modified { handle } = e {
+            if *handle == asset_server.get_handle("levels.json") {
+                console.logln("levels.json changed, reloading levels".into());
+                director.ready = false;
+            }
+        }
+    }
+
     if director.ready == false {
         let handle:Handle<Json> = asset_server.get_handle("levels.json");
         if asset_cache.contains(&handle) == false {
@@ -163,15 +358,18 @@ fn load_director(mut director:ResMut<Director>, asset_server:Res<AssetServer>, j
             asset_cache.track(&handle);
         } else if asset_cache.is_loaded(&handle) {
             let json = json.get(handle).unwrap();
-            if let Some(maps) = json.as_object().and_then(|o|o.get("maps").and_then(|v| v.as_array())) {
-                director.levels.maps.clear();
-                for map in maps {
-                    if let Some(name) = map.as_str() {
-                        director.levels.maps.push(name.into());
-                    }
+            match serde_json::from_value::<Levels>(json.0.clone()) {
+                Ok(levels) => director.levels = levels,
+                Err(err) => {
+                    // keep the levels already loaded, if any
+                    warn!("invalid levels.json: {}", err);
+                    console.logln(format!("invalid levels.json: {}", err));
                 }
             }
 
+            // the level being played may no longer exist
+            director.current_level = director.current_level.min(director.levels.count()).max(1);
+
             director.ready = true;
         }
     }
@@ -186,6 +384,9 @@ impl Plugin for DirectorPlugin {
         .insert_resource(Director::default())
         .add_startup_system(startup.system())
         .add_system(load_director.system())
-        .add_system_set(SystemSet::on_update(AppState::InGame).with_system(update.system()));
+        .add_system_set(SystemSet::on_update(AppState::InGame).with_system(update.system()).with_system(arm_players.system()))
+        .add_system_set(SystemSet::on_enter(AppState::LevelSelect).with_system(enter_level_select.system()))
+        .add_system_set(SystemSet::on_update(AppState::LevelSelect).with_system(level_select_ui.system()))
+        .add_system_set(SystemSet::on_update(GameState::Running).with_system(music_intensity.system()));
     }
 }
\ No newline at end of file
@@ -2,5 +2,7 @@ use bevy::prelude::*;
 
 pub struct ApplyDamageEvent {
     pub target:Entity,
-    pub amount:f32
+    pub amount:f32,
+    // entity dealing the damage, if any
+    pub source:Option<Entity>
 }
\ No newline at end of file
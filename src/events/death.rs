@@ -0,0 +1,10 @@
+use bevy::prelude::*;
+
+// sent when something runs out of health, just before it is despawned
+#[derive(Debug, Clone, Copy)]
+pub struct DeathEvent {
+    pub entity:Entity,
+    // entity that dealt the final damage, if known
+    pub killer:Option<Entity>,
+    pub position:Vec3
+}
@@ -0,0 +1,3 @@
+// sent when the current map has been rebuilt because its file changed on disk
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MapReloadedEvent;
@@ -9,6 +9,18 @@ pub use projectile_hit::*;
 mod apply_damage;
 pub use apply_damage::*;
 
+mod noise;
+pub use noise::*;
+
+mod map_reloaded;
+pub use map_reloaded::*;
+
+mod turret_fire;
+pub use turret_fire::*;
+
+mod death;
+pub use death::*;
+
 #[derive(Default)]
 pub struct EventsPlugin {
 }
@@ -18,6 +30,10 @@ impl Plugin for EventsPlugin {
         app
         .add_event::<NewGameEvent>()
         .add_event::<ProjectileHitEvent>()
-        .add_event::<ApplyDamageEvent>();
+        .add_event::<ApplyDamageEvent>()
+        .add_event::<NoiseEvent>()
+        .add_event::<MapReloadedEvent>()
+        .add_event::<TurretFireEvent>()
+        .add_event::<DeathEvent>();
     }
 }
\ No newline at end of file
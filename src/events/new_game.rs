@@ -1,11 +1,15 @@
+use rand::random;
+
 pub struct NewGameEvent {
-    pub map_size:usize
+    pub map_size:usize,
+    pub seed:u64
 } 
 
 impl Default for NewGameEvent {
     fn default() -> Self {
         NewGameEvent {
-            map_size:16
+            map_size:16,
+            seed:random()
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,29 @@
+use bevy::prelude::*;
+
+// something bots can hear, such as a gunshot or an explosion
+#[derive(Debug, Clone, Copy)]
+pub struct NoiseEvent {
+    pub position:Vec3,
+    // distance at which the noise can be heard
+    pub range:f32,
+    // entity making the noise, if it still exists
+    pub source:Option<Entity>
+}
+
+impl NoiseEvent {
+    pub fn gunshot(position:Vec3, source:Entity) -> Self {
+        Self {
+            position,
+            range:10.0,
+            source:Some(source)
+        }
+    }
+
+    pub fn explosion(position:Vec3) -> Self {
+        Self {
+            position,
+            range:14.0,
+            source:None
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use bevy::prelude::*;
+
+// sent when a turret fires a projectile
+#[derive(Debug, Clone, Copy)]
+pub struct TurretFireEvent {
+    // tank the turret is mounted on
+    pub tank:Entity,
+    pub position:Vec3
+}
@@ -0,0 +1,120 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use crate::{Autopilot, Config, Player, Tank, Turret, WaypointEvent};
+
+// distance from the tank at which the right stick places the turret target
+const AIM_DISTANCE:f32 = 4.0;
+
+#[derive(Default)]
+pub struct GamepadLobby {
+    pub gamepads:Vec<Gamepad>
+}
+
+pub fn gamepad_connection_system(mut lobby:ResMut<GamepadLobby>, mut gamepad_events:EventReader<GamepadEvent>, mut players:Query<&mut Player>) {
+    for GamepadEvent(gamepad, event_type) in gamepad_events.iter() {
+        match event_type {
+            GamepadEventType::Connected => {
+                if lobby.gamepads.contains(gamepad) == false {
+                    info!("{:?} connected", gamepad);
+                    lobby.gamepads.push(*gamepad);
+                }
+            }
+            GamepadEventType::Disconnected => {
+                info!("{:?} disconnected", gamepad);
+                lobby.gamepads.retain(|g| g != gamepad);
+            }
+            _ => {}
+        }
+    }
+
+    // release gamepads which are no longer connected
+    for mut player in players.iter_mut() {
+        if let Some(id) = player.gamepad {
+            if lobby.gamepads.contains(&Gamepad(id)) == false {
+                player.gamepad = None;
+            }
+        }
+    }
+
+    // assign free gamepads to players in the order they were connected
+    let mut free:Vec<Gamepad> = lobby.gamepads.iter().copied().filter(|g| {
+        players.iter_mut().all(|p| p.gamepad != Some(g.0))
+    }).collect();
+    for mut player in players.iter_mut() {
+        if player.gamepad.is_none() && free.len() > 0 {
+            player.gamepad = Some(free.remove(0).0);
+        }
+    }
+}
+
+pub fn gamepad_input_system(config:Res<Config>,
+    buttons:Res<Input<GamepadButton>>,
+    axes:Res<Axis<GamepadAxis>>,
+    button_axes:Res<Axis<GamepadButton>>,
+    mut players:Query<(&Player, &mut Tank, &mut Autopilot, &Children, &Transform)>,
+    mut turrets:Query<&mut Turret>,
+    mut waypoint_event_writer:EventWriter<WaypointEvent>) {
+    let deadzone = config.gamepad_deadzone();
+    let tank_mode = config.gamepad_drive_mode() == "tank";
+    for (player, mut tank, mut autopilot, children, transform) in players.iter_mut() {
+        let gamepad = match player.gamepad {
+            Some(id) => Gamepad(id),
+            None => continue
+        };
+
+        let stick = |x:GamepadAxisType, y:GamepadAxisType| {
+            let v = Vec2::new(
+                axes.get(GamepadAxis(gamepad, x)).unwrap_or(0.0),
+                axes.get(GamepadAxis(gamepad, y)).unwrap_or(0.0)
+            );
+            if v.length() < deadzone { Vec2::ZERO } else { v }
+        };
+
+        let left = stick(GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY);
+        let right = stick(GamepadAxisType::RightStickX, GamepadAxisType::RightStickY);
+
+        // drive
+        if left != Vec2::ZERO {
+            let (right_track, left_track) = if tank_mode {
+                (left.y - left.x, left.y + left.x)
+            } else {
+                arcade_tracks(left, transform)
+            };
+            tank.tracks[0] = right_track.max(-1.0).min(1.0);
+            tank.tracks[1] = left_track.max(-1.0).min(1.0);
+
+            if autopilot.waypoints.len() > 0 || autopilot.planning {
+                autopilot.clear();
+                waypoint_event_writer.send(WaypointEvent::Clear);
+            }
+        } else if player.keyboard_mouse == false && autopilot.waypoints.len() == 0 {
+            tank.tracks = Vec2::ZERO;
+        }
+
+        // aim and fire
+        let trigger = buttons.pressed(GamepadButton(gamepad, GamepadButtonType::RightTrigger2))
+            || button_axes.get(GamepadButton(gamepad, GamepadButtonType::RightTrigger2)).unwrap_or(0.0) > 0.5
+            || buttons.pressed(GamepadButton(gamepad, GamepadButtonType::RightTrigger));
+        for e in children.iter() {
+            if let Ok(mut turret) = turrets.get_component_mut::<Turret>(*e) {
+                if right != Vec2::ZERO {
+                    turret.target = transform.translation + right.normalize().extend(0.0) * AIM_DISTANCE;
+                }
+
+                // keyboard and mouse players might already have pulled the trigger this frame
+                turret.trigger = (player.keyboard_mouse && turret.trigger) || trigger;
+            }
+        }
+    }
+}
+
+// returns the throttle of the right and left track needed to drive in the direction of the stick
+pub(super) fn arcade_tracks(stick:Vec2, transform:&Transform) -> (f32, f32) {
+    let f = (transform.rotation * Vec3::new(1.0, 0.0, 0.0)).truncate();
+    let d = stick.normalize();
+    let angle = (f.x * d.y - f.y * d.x).atan2(f.dot(d));
+    let turn = (angle / (PI / 4.0)).max(-1.0).min(1.0);
+    let throttle = stick.length().min(1.0) * angle.cos().max(0.0);
+    (throttle + turn, throttle - turn)
+}
@@ -8,6 +8,12 @@ use input::*;
 mod waypoints_marker;
 use waypoints_marker::*;
 
+mod gamepad;
+pub use gamepad::*;
+
+mod touch;
+pub use touch::*;
+
 pub struct InputPlugin;
 
 #[derive(Debug)]
@@ -20,14 +26,23 @@ pub enum WaypointEvent {
 #[derive(Hash, Copy, Clone, PartialEq, Eq, Debug, SystemLabel)]
 pub struct InputSystem;
 
+#[derive(Hash, Copy, Clone, PartialEq, Eq, Debug, SystemLabel)]
+pub struct GamepadInputSystem;
+
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_event::<WaypointEvent>();
         app.insert_resource(WaypointMarkerSprites::default());
+        app.insert_resource(GamepadLobby::default());
+        app.insert_resource(TouchControls::default());
+        app.add_system_to_stage(CoreStage::PreUpdate, gamepad_connection_system.system());
         app.add_system_set_to_stage(CoreStage::Update, 
             SystemSet::on_update(AppState::InGame)
             .with_system(input_system.system().label(InputSystem).after(MouseSystem))
-            .with_system(waypoints_marker_system.system().after(InputSystem))
+            .with_system(gamepad_input_system.system().label(GamepadInputSystem).after(InputSystem))
+            .with_system(touch_input_system.system().after(GamepadInputSystem))
+            .with_system(touch_controls_ui_system.system())
+            .with_system(waypoints_marker_system.system().after(GamepadInputSystem))
         );
     }
 }
\ No newline at end of file
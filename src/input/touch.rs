@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContext, egui::{Color32, Id, LayerId, Order, Pos2, Stroke}};
+
+use crate::{Autopilot, Player, Tank, Turret, WaypointEvent};
+
+use super::arcade_tracks;
+
+// radius of the virtual joystick and the fire button in logical pixels
+pub const JOYSTICK_RADIUS:f32 = 64.0;
+pub const FIRE_RADIUS:f32 = 48.0;
+const MARGIN:f32 = 24.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchRole {
+    Joystick,
+    Fire,
+    Pointer
+}
+
+// state of the on-screen controls, positions are in screen space with y pointing up
+#[derive(Debug, Clone, Copy)]
+pub struct TouchControls {
+    pub enabled:bool,
+    pub joystick_origin:Option<Vec2>,
+    pub joystick:Vec2,
+    pub fire:bool
+}
+
+impl Default for TouchControls {
+    fn default() -> Self {
+        Self {
+            enabled:false,
+            joystick_origin:None,
+            joystick:Vec2::ZERO,
+            fire:false
+        }
+    }
+}
+
+impl TouchControls {
+    pub fn joystick_center(_width:f32, _height:f32) -> Vec2 {
+        Vec2::new(MARGIN + JOYSTICK_RADIUS, MARGIN + JOYSTICK_RADIUS)
+    }
+
+    pub fn fire_center(width:f32, _height:f32) -> Vec2 {
+        Vec2::new(width - MARGIN - FIRE_RADIUS, MARGIN + FIRE_RADIUS)
+    }
+
+    // decides what a touch starting at p controls
+    pub fn role_at(p:Vec2, width:f32, height:f32) -> TouchRole {
+        if p.distance(Self::fire_center(width, height)) <= FIRE_RADIUS * 1.25 {
+            TouchRole::Fire
+        } else if p.distance(Self::joystick_center(width, height)) <= JOYSTICK_RADIUS * 1.5 {
+            TouchRole::Joystick
+        } else {
+            TouchRole::Pointer
+        }
+    }
+
+    pub fn begin_joystick(&mut self, p:Vec2) {
+        self.joystick_origin = Some(p);
+        self.joystick = Vec2::ZERO;
+    }
+
+    pub fn move_joystick(&mut self, p:Vec2) {
+        if let Some(origin) = self.joystick_origin {
+            let d = (p - origin) / JOYSTICK_RADIUS;
+            self.joystick = if d.length() > 1.0 { d.normalize() } else { d };
+        }
+    }
+
+    pub fn end_joystick(&mut self) {
+        self.joystick_origin = None;
+        self.joystick = Vec2::ZERO;
+    }
+}
+
+pub fn touch_input_system(touch:Res<TouchControls>,
+    mut players:Query<(&Player, &mut Tank, &mut Autopilot, &Children, &Transform)>,
+    mut turrets:Query<&mut Turret>,
+    mut waypoint_event_writer:EventWriter<WaypointEvent>) {
+    if touch.enabled == false {
+        return;
+    }
+
+    for (player, mut tank, mut autopilot, children, transform) in players.iter_mut() {
+        if player.keyboard_mouse == false {
+            continue;
+        }
+
+        if touch.joystick.length() > 0.1 {
+            let (right_track, left_track) = arcade_tracks(touch.joystick, transform);
+            tank.tracks[0] = right_track.max(-1.0).min(1.0);
+            tank.tracks[1] = left_track.max(-1.0).min(1.0);
+
+            if autopilot.waypoints.len() > 0 || autopilot.planning {
+                autopilot.clear();
+                waypoint_event_writer.send(WaypointEvent::Clear);
+            }
+        }
+
+        if touch.fire {
+            for e in children.iter() {
+                if let Ok(mut turret) = turrets.get_component_mut::<Turret>(*e) {
+                    turret.trigger = true;
+                }
+            }
+        }
+    }
+}
+
+pub fn touch_controls_ui_system(egui_context:Res<EguiContext>, touch:Res<TouchControls>, windows:Res<Windows>) {
+    if touch.enabled == false {
+        return;
+    }
+
+    if let Some(window) = windows.get_primary() {
+        let (width, height) = (window.width(), window.height());
+        // egui has y pointing down
+        let to_pos = |p:Vec2| Pos2::new(p.x, height - p.y);
+        let painter = egui_context.ctx().layer_painter(LayerId::new(Order::Foreground, Id::new("touch_controls")));
+
+        let center = touch.joystick_origin.unwrap_or(TouchControls::joystick_center(width, height));
+        let knob = center + touch.joystick * JOYSTICK_RADIUS;
+        painter.circle_stroke(to_pos(center), JOYSTICK_RADIUS, Stroke::new(2.0, Color32::from_white_alpha(96)));
+        painter.circle_filled(to_pos(knob), JOYSTICK_RADIUS / 3.0, Color32::from_white_alpha(128));
+
+        let fire = TouchControls::fire_center(width, height);
+        let alpha = if touch.fire { 160 } else { 64 };
+        painter.circle_filled(to_pos(fire), FIRE_RADIUS, Color32::from_rgba_unmultiplied(255, 64, 64, alpha));
+        painter.circle_stroke(to_pos(fire), FIRE_RADIUS, Stroke::new(2.0, Color32::from_white_alpha(96)));
+    }
+}
@@ -1,18 +1,35 @@
-use bevy::{prelude::*, utils::HashMap};
+use bevy::prelude::*;
 
-use crate::{GamePiece, Waypoint, WaypointEvent};
+use crate::{Autopilot, GamePiece, Waypoint, WaypointEvent};
+
+const PATH_WIDTH:f32 = 0.05;
+// above the tilemap and track marks but below tanks, the path is drawn just under the markers
+const MARKER_Z:f32 = 0.2;
+const PATH_Z:f32 = 0.15;
 
 #[derive(Default)]
 pub struct WaypointMarkerSprites { 
-    pub sprites:HashMap<Vec3, Entity>
+    pub path_material:Option<Handle<ColorMaterial>>
 }
 
-pub fn waypoints_marker_system(mut commands:Commands, mut waypoint_event_reader:EventReader<WaypointEvent>, asset_server:Res<AssetServer>, mut materials:ResMut<Assets<ColorMaterial>>, query:Query<(Entity, &Waypoint)>) {
+// segment of the line connecting the waypoints
+pub struct PathSegment;
+
+pub fn waypoints_marker_system(mut commands:Commands, 
+    mut waypoint_event_reader:EventReader<WaypointEvent>, 
+    asset_server:Res<AssetServer>, 
+    mut materials:ResMut<Assets<ColorMaterial>>, 
+    mut marker_sprites:ResMut<WaypointMarkerSprites>,
+    query:Query<(Entity, &Waypoint)>,
+    segments:Query<Entity, With<PathSegment>>,
+    autopilots:Query<&Autopilot>) {
+    let mut changed = false;
     for e in waypoint_event_reader.iter() {
+        changed = true;
         match e {
             WaypointEvent::Added(w) => {
                 let mut l = w.location;
-                l.z = 0.0;
+                l.z = MARKER_Z;
                 let texture_handle = asset_server.load("imgs/waypoint.png");
                 let mut transform = Transform::from_translation(l);
                 let s = 1.0/8.0;
@@ -40,4 +57,30 @@ pub fn waypoints_marker_system(mut commands:Commands, mut waypoint_event_reader:
         }
        
     }
+
+    // rebuild the path line whenever the waypoints changed
+    if changed {
+        for e in segments.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+
+        let material = marker_sprites.path_material.get_or_insert_with(|| materials.add(Color::rgba(1.0, 1.0, 1.0, 0.5).into())).clone();
+        for autopilot in autopilots.iter() {
+            let points:Vec<Vec3> = autopilot.waypoints.iter().map(|w| w.location).collect();
+            for pair in points.windows(2) {
+                let (a, b) = (pair[0].truncate(), pair[1].truncate());
+                let d = b - a;
+                let mut transform = Transform::from_translation(((a + b) / 2.0).extend(PATH_Z));
+                transform.rotation = Quat::from_rotation_z(d.y.atan2(d.x));
+                commands.spawn_bundle(SpriteBundle {
+                    sprite:Sprite::new(Vec2::new(d.length(), PATH_WIDTH)),
+                    material:material.clone(),
+                    transform,
+                    ..Default::default()
+                })
+                .insert(GamePiece)
+                .insert(PathSegment);
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -48,12 +48,36 @@ pub use rapier::*;
 mod config;
 pub use config::*;
 
-mod exit;
-pub use exit::*;
+mod pause;
+pub use pause::*;
 
 mod input;
 pub use input::*;
 
+mod actions;
+pub use actions::*;
+
+mod settings;
+pub use settings::*;
+
+mod rts;
+pub use rts::*;
+
+mod bot_presets;
+pub use bot_presets::*;
+
+mod profile;
+pub use profile::*;
+
+mod main_menu;
+pub use main_menu::*;
+
+mod stats;
+pub use stats::*;
+
+mod sound_bank;
+pub use sound_bank::*;
+
 mod preload;
 
 #[cfg(target_arch = "wasm32")]
@@ -64,8 +88,11 @@ mod native;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum AppState {
-    ShowExit,
+    Paused,
     Splash,
+    MainMenu,
+    Options,
+    LevelSelect,
     InGame
 }
 
@@ -89,18 +116,18 @@ impl Default for GameState {
 
 
 
-fn debug(mut char_input_reader:EventReader<ReceivedCharacter>, mut console:ResMut<Console>, config:Res<Config>) {
+fn debug(actions:Res<Actions>, mut console:ResMut<Console>, config:Res<Config>) {
     if config.debug() {
-        for e in char_input_reader.iter() {
-            if ['1', '2', '3', '4', '5', '6', '7', '8', '9'].contains(&e.char) {
-                console.load_map(e.char.to_string().as_str());
+        for i in 1..=9 {
+            if actions.just_pressed(Action::LoadMap(i)) {
+                console.load_map(i.to_string().as_str());
             }
         }
     }
 }
 
-fn state_input(input:Res<Input<KeyCode>>, mut hud:ResMut<Hud>) {
-    if input.just_pressed(KeyCode::F1) || input.just_pressed(KeyCode::Grave) {
+fn state_input(actions:Res<Actions>, mut hud:ResMut<Hud>) {
+    if actions.just_pressed(Action::ToggleConsole) {
         hud.show_console = !hud.show_console;
     }
 }
@@ -108,7 +135,7 @@ fn state_input(input:Res<Input<KeyCode>>, mut hud:ResMut<Hud>) {
 fn startup_system(mut commands:Commands, mut rapier:ResMut<RapierConfiguration>, mut app_state:ResMut<State<AppState>>) {
     // cameras
     commands.spawn_bundle(UiCameraBundle::default());
-    commands.spawn_bundle(OrthographicCameraBundle::new_2d()).insert(GameCamera::default());
+    commands.spawn_bundle(OrthographicCameraBundle::new_2d()).insert(GameCamera::default()).insert(AudioListener);
 
     rapier.gravity.x = 0.0;
     rapier.gravity.y = 0.0;
@@ -129,8 +156,8 @@ pub fn start() {
     let mut builder = App::build();
     let window = WindowDescriptor {
         title: "Some Tank Game!".to_string(),
-        width: 1024.0,
-        height: 768.0,
+        width: config.width() as f32,
+        height: config.height() as f32,
         vsync: config.vsync(),
         resize_constraints:WindowResizeConstraints {
             min_width: config.width() as f32 / 4.0,
@@ -144,6 +171,13 @@ pub fn start() {
     };
 
     builder.insert_resource(window);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    builder.insert_resource(bevy::asset::AssetServerSettings {
+        watch_for_changes:config.hot_reload(),
+        ..Default::default()
+    });
+
     builder.add_plugins(DefaultPlugins);
     
     #[cfg(not(target_arch = "wasm32"))]
@@ -178,26 +212,36 @@ pub fn start() {
     .add_plugin(AudioPlugin)
     .add_plugin(JsonLoaderPlugin)
     .add_plugin(AssetCachePlugin)
-    .add_plugin(ExitPlugin)
+    .add_plugin(PausePlugin)
     .add_plugin(MousePlugin)
-    .add_plugin(InputPlugin);
+    .add_plugin(InputPlugin)
+    .add_plugin(ActionsPlugin)
+    .add_plugin(SettingsPlugin)
+    .add_plugin(RtsPlugin)
+    .add_plugin(BotPresetsPlugin)
+    .add_plugin(SoundBankPlugin)
+    .add_plugin(ProfilePlugin)
+    .add_plugin(MainMenuPlugin)
+    .add_plugin(StatsPlugin);
 
 
     
     // add resources
     builder
-    .insert_resource(config);
+    .insert_resource(config.audio_mixer())
+    .insert_resource(config)
+    .insert_resource(TeamIntel::default());
 
     // add events
     builder.add_event::<NewGameEvent>();
 
     // add startup systems
     builder
-    .add_startup_system(startup_system.system())
-    .add_startup_system(preload::preload.system());
+    .add_startup_system(startup_system.system());
 
     // add always on systems
     builder
+    .add_system(preload::preload.system())
     .add_system(camera_system.system())
     .add_system(faction_system.system());
 
@@ -207,9 +251,9 @@ pub fn start() {
         .with_system(turret_system.system())
         .with_system(bot_system.system())
         .with_system(bot_sensor_system.system())
-        .with_system(projectile_system.system().after("physics"))
+        .with_system(projectile_system.system().label("projectile").after("physics"))
         .with_system(physics_system.system().label("physics"))
-        .with_system(health_system.system())
+        .with_system(health_system.system().label("health"))
         .with_system(tank_system.system())
         .with_system(effect_system.system())
     );
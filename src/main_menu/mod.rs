@@ -0,0 +1,168 @@
+use bevy::{app::AppExit, prelude::*, window::WindowMode};
+use bevy_egui::{EguiContext, egui::{self, Align2}};
+
+use crate::{AppState, AudioMixer, Bindings, Config, DelayState, Director, Hud, Profile, Settings, audio_ui, controls_ui};
+
+// resolutions offered in the options, the one from config.ini is added if missing
+const RESOLUTIONS:[(u32, u32); 5] = [(800, 600), (1024, 768), (1280, 720), (1600, 900), (1920, 1080)];
+
+fn enter_menu(mut hud:ResMut<Hud>) {
+    hud.clear_texts();
+}
+
+fn main_menu_ui(egui_context:Res<EguiContext>,
+    mut director:ResMut<Director>,
+    profile:Res<Profile>,
+    config:Res<Config>,
+    mut app_state:ResMut<DelayState<AppState>>,
+    mut state:ResMut<State<AppState>>,
+    mut hud:ResMut<Hud>,
+    mut exit:EventWriter<AppExit>) {
+    if app_state.has_state() {
+        return;
+    }
+
+    let mut level = None;
+    let mut quit = false;
+    egui::Window::new("Some Tank Game!")
+    .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+    .collapsible(false)
+    .resizable(false)
+    .show(egui_context.ctx(), |ui| {
+        ui.vertical_centered_justified(|ui| {
+            if ui.add(egui::Button::new("New Game").enabled(director.ready)).clicked() {
+                level = Some(1);
+            }
+            if ui.add(egui::Button::new("Continue").enabled(director.ready && profile.has_progress())).clicked() {
+                level = Some(profile.next_level(&director.levels));
+            }
+            if ui.button("Level Select").clicked() {
+                app_state.set(AppState::LevelSelect, 0.0);
+            }
+            // options return to whatever screen opened them
+            if ui.button("Options").clicked() {
+                let _ = state.push(AppState::Options);
+            }
+
+            // browsers do not let a page close itself
+            if cfg!(not(target_arch = "wasm32")) {
+                quit = ui.button("Quit").clicked();
+            }
+        });
+    });
+
+    if quit {
+        exit.send(AppExit);
+    }
+
+    if let Some(level) = level {
+        director.start_level(level);
+        let time = if config.quick() == false { 0.5 } else { 0.0 };
+        app_state.set(AppState::InGame, time);
+        if config.quick() == false {
+            hud.fade(time, time, Color::BLACK);
+        }
+    }
+}
+
+// resolution, fullscreen and vsync, applied to the window right away
+fn display_ui(ui:&mut egui::Ui, window:&mut Window, config:&mut Config) {
+    let mut resolutions = RESOLUTIONS.to_vec();
+    let current = (config.width(), config.height());
+    if resolutions.contains(&current) == false {
+        resolutions.push(current);
+    }
+
+    let mut resolution = current;
+    let mut fullscreen = config.fullscreen();
+    let mut vsync = config.vsync();
+    egui::Grid::new("Display").show(ui, |ui| {
+        ui.label("Resolution");
+        ui.horizontal_wrapped(|ui| {
+            for r in resolutions.iter() {
+                ui.selectable_value(&mut resolution, *r, format!("{}x{}", r.0, r.1));
+            }
+        });
+        ui.end_row();
+
+        ui.label("Fullscreen");
+        ui.checkbox(&mut fullscreen, "");
+        ui.end_row();
+
+        ui.label("Vsync");
+        ui.checkbox(&mut vsync, "");
+        ui.end_row();
+    });
+
+    if resolution != current {
+        config.set_value("width", &resolution.0.to_string());
+        config.set_value("height", &resolution.1.to_string());
+        window.set_resolution(resolution.0 as f32, resolution.1 as f32);
+        config.save();
+    }
+    if fullscreen != config.fullscreen() {
+        config.set_value("fullscreen", &fullscreen.to_string());
+        window.set_mode(if fullscreen { WindowMode::BorderlessFullscreen } else { WindowMode::Windowed });
+        config.save();
+    }
+    if vsync != config.vsync() {
+        config.set_value("vsync", &vsync.to_string());
+        window.set_vsync(vsync);
+        config.save();
+    }
+}
+
+fn options_ui(egui_context:Res<EguiContext>,
+    mut settings:ResMut<Settings>,
+    mut bindings:ResMut<Bindings>,
+    mut config:ResMut<Config>,
+    mut mixer:ResMut<AudioMixer>,
+    mut windows:ResMut<Windows>,
+    mut app_state:ResMut<State<AppState>>) {
+    let mut back = false;
+    egui::Window::new("Options")
+    .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+    .collapsible(false)
+    .resizable(false)
+    .show(egui_context.ctx(), |ui| {
+        // the size of the canvas follows the browser window
+        if cfg!(not(target_arch = "wasm32")) {
+            if let Some(window) = windows.get_primary_mut() {
+                ui.heading("Display");
+                display_ui(ui, window, &mut config);
+                ui.separator();
+            }
+        }
+
+        ui.heading("Audio");
+        // only touch the mixer when a volume changes, such that the channels are not updated every frame
+        let mut volumes = *mixer;
+        audio_ui(ui, &mut volumes, &mut config);
+        if volumes.master != mixer.master || volumes.music != mixer.music || volumes.sfx != mixer.sfx || volumes.ui != mixer.ui {
+            *mixer = volumes;
+        }
+
+        ui.separator();
+        ui.heading("Controls");
+        controls_ui(ui, &mut settings, &mut bindings, &mut config);
+
+        ui.separator();
+        if ui.add(egui::Button::new("Back").enabled(settings.rebinding.is_none())).clicked() {
+            back = true;
+        }
+    });
+
+    if back {
+        let _ = app_state.pop();
+    }
+}
+
+pub struct MainMenuPlugin;
+
+impl Plugin for MainMenuPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(enter_menu.system()));
+        app.add_system_set(SystemSet::on_update(AppState::MainMenu).with_system(main_menu_ui.system()));
+        app.add_system_set(SystemSet::on_update(AppState::Options).with_system(options_ui.system()));
+    }
+}
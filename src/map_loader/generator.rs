@@ -0,0 +1,239 @@
+use std::{collections::VecDeque, f32::consts::PI};
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::{Tile, Tilemap};
+
+use super::Spawn;
+
+const FLOOR:u32 = 0;
+pub(super) const WALL:u32 = 1;
+
+// generates a random skirmish map using cellular automata caves.
+// the cave is rebuilt from 2x2 tile blocks, the room a tank needs to pass, and only the largest group of blocks
+// connected through their sides is kept. this removes one tile gaps and diagonal pinches, such that all spawns can reach each other
+pub fn generate(size:usize, seed:u64) -> (Tilemap, Vec<Spawn>) {
+    let size = size.max(8);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut blocked = Vec::new();
+    let mut region = Vec::new();
+
+    // retry until a large enough cave has been carved out
+    for _ in 0..16 {
+        blocked = blocks(size, &caves(size, &mut rng));
+        region = largest_region(size, &blocked);
+        if region.len() >= size * size / 4 {
+            break;
+        }
+    }
+
+    // only the tiles of the reachable blocks are floor
+    let mut solid = vec![true; size * size];
+    for i in region.iter() {
+        for j in [*i, *i + 1, *i + size, *i + size + 1].iter() {
+            solid[*j] = false;
+        }
+    }
+    let mut blocked = vec![true; size * size];
+    for i in region.iter() {
+        blocked[*i] = false;
+    }
+
+    let mut tilemap = Tilemap::new(size, 4, "imgs/tiles.png");
+    for y in 0..size {
+        for x in 0..size {
+            let s = solid[y * size + x];
+            tilemap.set_tile(Tile {
+                index:if s { WALL } else { FLOOR },
+                solid:s,
+                ..Default::default()
+            }, x, y);
+        }
+    }
+
+    let spawns = spawns(size, &blocked, &region, &mut rng);
+    (tilemap, spawns)
+}
+
+// a block at x, y covers the tiles x..x+2, y..y+2 and is blocked unless all of them are floor
+fn blocks(size:usize, solid:&[bool]) -> Vec<bool> {
+    (0..size * size).map(|i| {
+        let (x, y) = (i % size, i / size);
+        x + 1 >= size || y + 1 >= size || solid[i] || solid[i + 1] || solid[i + size] || solid[i + size + 1]
+    }).collect()
+}
+
+fn caves(size:usize, rng:&mut StdRng) -> Vec<bool> {
+    let border = |x:usize, y:usize| x == 0 || y == 0 || x == size - 1 || y == size - 1;
+    let mut solid:Vec<bool> = (0..size * size).map(|i| {
+        border(i % size, i / size) || rng.gen_bool(0.42)
+    }).collect();
+
+    for _ in 0..4 {
+        let mut next = solid.clone();
+        for y in 0..size {
+            for x in 0..size {
+                if border(x, y) {
+                    continue;
+                }
+
+                let walls = neighbours(size, &solid, x, y);
+                let i = y * size + x;
+                if walls > 4 {
+                    next[i] = true;
+                } else if walls < 4 {
+                    next[i] = false;
+                }
+            }
+        }
+        solid = next;
+    }
+
+    solid
+}
+
+fn neighbours(size:usize, solid:&[bool], x:usize, y:usize) -> u32 {
+    let mut count = 0;
+    for dy in -1..=1 as i32 {
+        for dx in -1..=1 as i32 {
+            if dx == 0 && dy == 0 {
+                continue;
+            }
+            let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+            if nx < 0 || ny < 0 || nx >= size as i32 || ny >= size as i32 || solid[ny as usize * size + nx as usize] {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+fn flood(size:usize, solid:&[bool], start:usize, visited:&mut [bool]) -> Vec<usize> {
+    let mut region = Vec::new();
+    let mut queue = VecDeque::new();
+    visited[start] = true;
+    queue.push_back(start);
+    while let Some(i) = queue.pop_front() {
+        region.push(i);
+        let (x, y) = (i % size, i / size);
+        let mut visit = |n:usize| {
+            if !solid[n] && !visited[n] {
+                visited[n] = true;
+                queue.push_back(n);
+            }
+        };
+        if x > 0 { visit(i - 1); }
+        if x < size - 1 { visit(i + 1); }
+        if y > 0 { visit(i - size); }
+        if y < size - 1 { visit(i + size); }
+    }
+    region
+}
+
+fn largest_region(size:usize, solid:&[bool]) -> Vec<usize> {
+    let mut visited = vec![false; size * size];
+    let mut largest = Vec::new();
+    for i in 0..solid.len() {
+        if !solid[i] && !visited[i] {
+            let region = flood(size, solid, i, &mut visited);
+            if region.len() > largest.len() {
+                largest = region;
+            }
+        }
+    }
+    largest
+}
+
+// spawns are placed on blocks, in the middle of the 2x2 tiles they cover
+fn spawns(size:usize, blocked:&[bool], region:&[usize], rng:&mut StdRng) -> Vec<Spawn> {
+    // prefer blocks surrounded by other blocks such that tanks have room to turn
+    let mut open:Vec<usize> = region.iter().copied().filter(|i| neighbours(size, blocked, i % size, i / size) == 0).collect();
+    if open.len() < 2 {
+        open = region.to_vec();
+    }
+    if open.len() == 0 {
+        return Vec::new();
+    }
+
+    let spawn = |i:usize, object_type:&str, rng:&mut StdRng| Spawn {
+        x:(i % size) as f32 + 1.0,
+        y:(i / size) as f32 + 1.0,
+        rotation:rng.gen_range(0.0..PI * 2.0),
+        object_type:object_type.into(),
+        properties:Default::default()
+    };
+
+    let player = open[rng.gen_range(0..open.len())];
+    let (px, py) = ((player % size) as f32, (player / size) as f32);
+    let distance = |i:&usize| {
+        let (x, y) = ((i % size) as f32, (i / size) as f32);
+        (x - px).powi(2) + (y - py).powi(2)
+    };
+
+    // place bots in the far half of the cave, seen from the player
+    let mut far:Vec<usize> = open.iter().copied().filter(|i| *i != player).collect();
+    far.sort_by(|a, b| distance(b).partial_cmp(&distance(a)).unwrap());
+    far.truncate((far.len() / 2).max(1));
+
+    let mut spawns = vec![spawn(player, "player", rng)];
+    let bots = (size / 6).max(1);
+    for _ in 0..bots {
+        if far.len() == 0 {
+            break;
+        }
+        let i = far.swap_remove(rng.gen_range(0..far.len()));
+        spawns.push(spawn(i, "bot", rng));
+
+        // keep some room between bots
+        let (x, y) = ((i % size) as i32, (i / size) as i32);
+        far.retain(|j| ((j % size) as i32 - x).abs() > 2 || ((j / size) as i32 - y).abs() > 2);
+    }
+
+    spawns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(tilemap:&Tilemap) -> Vec<bool> {
+        let size = tilemap.width();
+        (0..size * size).map(|i| tilemap.get_tile(i % size, i / size).unwrap().solid).collect()
+    }
+
+    #[test]
+    fn same_seed_same_map() {
+        for seed in [0, 1, 42, 1234567].iter() {
+            let (a, a_spawns) = generate(48, *seed);
+            let (b, b_spawns) = generate(48, *seed);
+            assert_eq!(solid(&a), solid(&b));
+            assert_eq!(a.tiles().iter().map(|t| t.index).collect::<Vec<_>>(), b.tiles().iter().map(|t| t.index).collect::<Vec<_>>());
+            assert_eq!(a_spawns.len(), b_spawns.len());
+            for (a, b) in a_spawns.iter().zip(b_spawns.iter()) {
+                assert_eq!((a.x, a.y, a.rotation, &a.object_type), (b.x, b.y, b.rotation, &b.object_type));
+            }
+        }
+    }
+
+    #[test]
+    fn spawns_reach_each_other() {
+        for size in [16, 32, 64].iter() {
+            for seed in 0..20 {
+                let (tilemap, spawns) = generate(*size, seed);
+                let size = tilemap.width();
+                assert!(spawns.iter().any(|s| s.object_type == "player"), "no player in map {} of size {}", seed, size);
+
+                // a tank in the middle of a spawn can move along 2x2 floor blocks connected through their sides
+                let blocked = blocks(size, &solid(&tilemap));
+                let block = |s:&Spawn| (s.y as usize - 1) * size + s.x as usize - 1;
+                let mut visited = vec![false; size * size];
+                let start = block(&spawns[0]);
+                assert!(blocked[start] == false, "spawn inside a wall in map {} of size {}", seed, size);
+                flood(size, &blocked, start, &mut visited);
+                for s in spawns.iter() {
+                    assert!(visited[block(s)], "{} at {}, {} can not be reached in map {} of size {}", s.object_type, s.x, s.y, seed, size);
+                }
+            }
+        }
+    }
+}
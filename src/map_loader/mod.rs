@@ -2,21 +2,59 @@ use std::f32::consts::PI;
 
 pub use bevy::prelude::*;
 use extensions::tiled;
-use crate::{GamePiece, Tile, Tilemap, TiledMap};
+use crate::{GamePiece, MapReloadedEvent, Terrain, Tile, TileAnimation, TileFrame, Tilemap, TiledMap};
 
 mod spawner;
 use spawner::*;
 
+mod generator;
+
 #[derive(Default)]
 pub struct MapLoader {
     pub(in self) next_map:Option<Handle<TiledMap>>,
-    pub(in self) current_map:Option<Handle<TiledMap>>
+    pub(in self) current_map:Option<Handle<TiledMap>>,
+    pub(in self) next_generated:Option<(usize, u64)>
 }
 
 impl MapLoader {
     pub fn load_map(&mut self, map_path:&str, asset_server:Res<AssetServer>) {
         self.next_map = Some(asset_server.load(map_path));
     }
+
+    pub fn generate_map(&mut self, size:usize, seed:u64) {
+        self.next_map = None;
+        self.next_generated = Some((size, seed));
+    }
+}
+
+fn map_generator(mut map_loader:ResMut<MapLoader>, game_pieces:Query<(Entity, &GamePiece)>, mut commands:Commands) {
+    if let Some((size, seed)) = map_loader.next_generated.take() {
+        map_loader.current_map = None;
+
+        // cleanup existing game pieces
+        game_pieces.for_each_mut(|e| {
+            let mut e = commands.entity(e.0);
+            e.despawn_recursive();
+        });
+
+        let (tilemap, spawns) = generator::generate(size, seed);
+        commands.spawn().insert(tilemap).insert(GamePiece::default());
+        for s in spawns {
+            spawn(&mut commands, s);
+        }
+    }
+}
+
+// rebuilds the current map when its file has been changed
+fn map_reloader(mut map_loader:ResMut<MapLoader>, mut events:EventReader<AssetEvent<TiledMap>>, mut reloaded:EventWriter<MapReloadedEvent>) {
+    for e in events.iter() {
+        if let AssetEvent::Modified { handle } = e {
+            if map_loader.current_map.as_ref() == Some(handle) && map_loader.next_map.is_none() {
+                map_loader.next_map = Some(handle.clone());
+                reloaded.send(MapReloadedEvent);
+            }
+        }
+    }
 }
 
 fn map_loader(mut map_loader:ResMut<MapLoader>, maps:Res<Assets<TiledMap>>, game_pieces:Query<(Entity, &GamePiece)>, mut commands:Commands) {
@@ -32,44 +70,65 @@ fn map_loader(mut map_loader:ResMut<MapLoader>, maps:Res<Assets<TiledMap>>, game
                 e.despawn_recursive();
             });
 
-            assert_eq!(map.width, map.height);
+            // tiled uses a y-down coordinate space, which for infinite maps can extend into negative coordinates.
+            // infinite maps get a border of one tile, such that the walls around them are inside the tilemap
+            let infinite = map.layers.iter().any(|l| matches!(l.tiles, tiled::LayerData::Infinite(_)));
+            let border = if infinite { 1 } else { 0 };
+            let (min_x, min_y, max_x, max_y) = map_bounds(map);
+            let (min_x, min_y, max_x, max_y) = (min_x - border, min_y - border, max_x + border, max_y + border);
+            let width = (max_x - min_x) as usize;
+            let height = (max_y - min_y) as usize;
+            // tiles within a chunk of an infinite layer, everything else becomes a wall
+            let mut covered = vec![!infinite; width * height];
 
             // create tilemap
-            let size = map.width as usize;
-            let mut tilemap = Tilemap::new(size, 4, "imgs/tiles.png");
+            let mut tilemap = Tilemap::with_size(width, height, 4, "imgs/tiles.png");
+            let mut set_tile = |x:i32, y:i32, layer_tile:&tiled::LayerTile| {
+                if let Some((tile, animation)) = load_tile(map, layer_tile) {
+                    if let Some(animation) = animation {
+                        if tilemap.animations().contains_key(&tile.index) == false {
+                            tilemap.set_animation(tile.index, animation);
+                        }
+                    }
+
+                    // flip row
+                    let flipped_row = max_y - y - 1;
+                    tilemap.set_tile(tile, (x - min_x) as usize, flipped_row as usize);
+                }
+            };
 
             for layer in map.layers.iter() {
                 match &layer.tiles {
                     tiled::LayerData::Finite(row) => {
                         for (row, col) in row.iter().enumerate() {
                             for (col, tile) in col.iter().enumerate() {
-                                let gid = tile.gid;
-                                let mut solid = false;
-                                let tileset = map.get_tileset_by_gid(gid).expect("tileset was not found");
-                                let id = gid - tileset.first_gid;
-                                let tile = tileset.tiles.iter().find(|tile| {
-                                    tile.id == id
-                                });
-                                if let Some(tile) = tile {
-                                    if let Some(tiled::PropertyValue::BoolValue(property)) = tile.properties.get("solid") {
-                                        solid = *property;
-                                    }
+                                set_tile(col as i32, row as i32, tile);
+                            }
+                        }
+                    },
+                    tiled::LayerData::Infinite(chunks) => {
+                        for chunk in chunks.values() {
+                            for (row, col) in chunk.tiles.iter().enumerate() {
+                                for (col, tile) in col.iter().enumerate() {
+                                    let (x, y) = (chunk.x + col as i32, chunk.y + row as i32);
+                                    covered[(max_y - y - 1) as usize * width + (x - min_x) as usize] = true;
+                                    set_tile(x, y, tile);
                                 }
-
-                                // flip row
-                                let flipped_row = map.height as usize - row - 1;
-                                tilemap.set_tile(Tile {
-                                    index: id,
-                                    solid,
-                                    ..Default::default()
-                                }, col, flipped_row);
                             }
                         }
                     },
-                    tiled::LayerData::Infinite(_) => panic!("infinite layers not supported"),
                 }
             }
 
+            // missing chunks and the border are solid, such that tanks can not drive off into empty space
+            for (i, _) in covered.iter().enumerate().filter(|(_, c)| **c == false) {
+                tilemap.set_tile(Tile {
+                    index:generator::WALL,
+                    solid:true,
+                    ..Default::default()
+                }, i % width, i / width);
+            }
+
             commands.spawn().insert(tilemap).insert(GamePiece::default());
 
             map.object_groups.iter().for_each(|grp| {
@@ -84,8 +143,8 @@ fn map_loader(mut map_loader:ResMut<MapLoader>, maps:Res<Assets<TiledMap>>, game
                     let rotated_center_y = center_x * sin_rot + center_y * cos_rot;
                     let x = obj.x + rotated_center_x;
                     let y = obj.y + rotated_center_y;
-                    let x = x / map.tile_width as f32;
-                    let y = (map.height as f32 * map.tile_height as f32 - y) / map.tile_height as f32;
+                    let x = x / map.tile_width as f32 - min_x as f32;
+                    let y = (max_y as f32 * map.tile_height as f32 - y) / map.tile_height as f32;
                     let gid = obj.gid;
                     let tileset = map.get_tileset_by_gid(gid).expect("tileset was not found");
                     let id = gid - tileset.first_gid;
@@ -99,22 +158,107 @@ fn map_loader(mut map_loader:ResMut<MapLoader>, maps:Res<Assets<TiledMap>>, game
 
                     // flip rotation since we change coordinate space
                     let rotation = (360.0 - obj.rotation) * PI/180.0;
+                    let properties = obj.properties.iter().filter_map(|(k, v)| {
+                        property_to_string(v).map(|v| (k.clone(), v))
+                    }).collect();
+
                     spawn(&mut commands, Spawn {
                         x,
                         y,
                         object_type,
-                        rotation
+                        rotation,
+                        properties
                     });
                 });
             })
         }
     }
 }
+// returns the tile bounds (min_x, min_y, max_x, max_y) of all layers in tiled coordinates
+fn map_bounds(map:&tiled::Map) -> (i32, i32, i32, i32) {
+    let mut bounds:Option<(i32, i32, i32, i32)> = None;
+    let mut include = |x0:i32, y0:i32, x1:i32, y1:i32| {
+        bounds = Some(match bounds {
+            Some((a, b, c, d)) => (a.min(x0), b.min(y0), c.max(x1), d.max(y1)),
+            None => (x0, y0, x1, y1)
+        });
+    };
+
+    for layer in map.layers.iter() {
+        match &layer.tiles {
+            tiled::LayerData::Finite(_) => include(0, 0, map.width as i32, map.height as i32),
+            tiled::LayerData::Infinite(chunks) => {
+                for chunk in chunks.values() {
+                    include(chunk.x, chunk.y, chunk.x + chunk.width as i32, chunk.y + chunk.height as i32);
+                }
+            }
+        }
+    }
+
+    bounds.unwrap_or((0, 0, map.width as i32, map.height as i32))
+}
+
+fn property_to_string(property:&tiled::PropertyValue) -> Option<String> {
+    match property {
+        tiled::PropertyValue::BoolValue(v) => Some(v.to_string()),
+        tiled::PropertyValue::FloatValue(v) => Some(v.to_string()),
+        tiled::PropertyValue::IntValue(v) => Some(v.to_string()),
+        tiled::PropertyValue::StringValue(v) => Some(v.clone()),
+        _ => None
+    }
+}
+
+fn load_tile(map:&tiled::Map, layer_tile:&tiled::LayerTile) -> Option<(Tile, Option<TileAnimation>)> {
+    let gid = layer_tile.gid;
+    if gid == 0 {
+        // empty tile
+        return None;
+    }
+
+    let mut solid = false;
+    let mut terrain = Terrain::default();
+    let mut animation = None;
+    let tileset = map.get_tileset_by_gid(gid).expect("tileset was not found");
+    let id = gid - tileset.first_gid;
+    let tile = tileset.tiles.iter().find(|tile| {
+        tile.id == id
+    });
+    if let Some(tile) = tile {
+        if let Some(tiled::PropertyValue::BoolValue(property)) = tile.properties.get("solid") {
+            solid = *property;
+        }
+
+        if let Some(tiled::PropertyValue::StringValue(property)) = tile.properties.get("terrain") {
+            terrain = property.as_str().into();
+        }
+
+        if let Some(frames) = &tile.animation {
+            animation = Some(TileAnimation {
+                frames:frames.iter().map(|f| TileFrame {
+                    index:f.tile_id,
+                    duration:f.duration as f32 / 1000.0
+                }).collect()
+            });
+        }
+    }
+
+    Some((Tile {
+        index: id,
+        solid,
+        terrain,
+        flip_h:layer_tile.flip_h,
+        flip_v:layer_tile.flip_v,
+        flip_d:layer_tile.flip_d
+    }, animation))
+}
+
 pub struct MapLoaderPlugin;
 
 impl Plugin for MapLoaderPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.insert_resource(MapLoader::default());
-        app.add_system(map_loader.system());
+        app.add_system(map_reloader.system().before("map_loader"));
+        app.add_system(map_loader.system().label("map_loader"));
+        app.add_system(map_generator.system());
     }
 }
\ No newline at end of file
@@ -1,16 +1,26 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 
-use crate::{Autopilot, Bot, Faction, Player, ThingBuilder, ThingType};
+use crate::{Autopilot, Bot, BotProperties, BotTuning, Faction, Player, TankClass, ThingBuilder, ThingType, Unit};
 
 pub struct Spawn {
     pub x:f32,
     pub y:f32,
     pub rotation:f32,
-    pub object_type:String
+    pub object_type:String,
+    pub properties:HashMap<String, String>
 }
 
 pub fn spawn(commands:&mut Commands, spawn:Spawn) {
     let (x, y) = (spawn.x, spawn.y);
+    let tank_class = spawn.properties.get("class").map(|c| TankClass::from(c.as_str())).unwrap_or_default();
+    // traverse limits of the turret in degrees, such as '-90,90'
+    let turret_traverse = spawn.properties.get("turret_traverse").and_then(|t| {
+        let mut limits = t.split(',').filter_map(|v| v.trim().parse::<f32>().ok());
+        match (limits.next(), limits.next()) {
+            (Some(min), Some(max)) => Some((min.min(max).to_radians(), min.max(max).to_radians())),
+            _ => None
+        }
+    });
     let rotation = Quat::from_rotation_z(spawn.rotation);
     let translation = Vec3::new(x, y, 0.0);
     match spawn.object_type.to_lowercase().as_str() {
@@ -19,10 +29,29 @@ pub fn spawn(commands:&mut Commands, spawn:Spawn) {
                 translation,
                 rotation,
                 thing_type:ThingType::Tank,
+                tank_class,
+                turret_traverse,
                 ..Default::default()
             })
             .insert(Player::default())
             .insert(Autopilot::default())
+            .insert(Unit::default())
+            .insert(Faction::Greens);
+        }
+        "ally" => {
+            commands.spawn().insert(ThingBuilder {
+                translation,
+                rotation,
+                thing_type:ThingType::Tank,
+                tank_class,
+                turret_traverse,
+                ..Default::default()
+            })
+            .insert(Bot::default())
+            .insert(BotTuning::default())
+            .insert(BotProperties { properties:spawn.properties.clone() })
+            .insert(Autopilot::default())
+            .insert(Unit::default())
             .insert(Faction::Greens);
         }
         "bot" => {
@@ -30,9 +59,13 @@ pub fn spawn(commands:&mut Commands, spawn:Spawn) {
                 translation,
                 rotation,
                 thing_type:ThingType::Tank,
+                tank_class,
+                turret_traverse,
                 ..Default::default()
             })
             .insert(Bot::default())
+            .insert(BotTuning::default())
+            .insert(BotProperties { properties:spawn.properties.clone() })
             .insert(Faction::Reds);
         }
         _ => {}
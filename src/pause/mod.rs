@@ -0,0 +1,125 @@
+use bevy::{app::AppExit, prelude::*};
+use bevy_egui::{EguiContext, egui::{self, Align2}};
+
+use crate::{Action, ActionSystem, Actions, AppState, AudioMixer, Director, GameState, Hud, MusicEvent, Settings};
+
+#[derive(Default)]
+pub struct Pause {
+    // true if the game was running when the pause menu was opened
+    pub paused_the_game:bool
+}
+pub struct PausePlugin;
+
+fn resume(pause:&Pause, hud:&mut Hud, app_state:&mut State<AppState>, game_state:&mut State<GameState>, mixer:&mut AudioMixer) {
+    if pause.paused_the_game {
+        if let Err(err) = game_state.set(GameState::Running) {
+            warn!("could not resume the game: {:?}", err);
+        }
+    }
+
+    mixer.paused = false;
+    hud.pop();
+    if let Err(err) = app_state.pop() {
+        warn!("could not close the pause menu: {:?}", err);
+    }
+}
+
+fn input(actions:Res<Actions>, settings:Res<Settings>, mut hud:ResMut<Hud>, mut app_state:ResMut<State<AppState>>, mut game_state:ResMut<State<GameState>>, mut pause:ResMut<Pause>, mut mixer:ResMut<AudioMixer>) {
+    if settings.rebinding.is_some() {
+        // keys are being captured by the settings
+        return;
+    }
+
+    if actions.just_pressed(Action::Pause) == false {
+        return;
+    }
+
+    match app_state.current().clone() {
+        AppState::InGame => {
+            hud.push();
+            hud.clear();
+            hud.background = Color::rgba(0.0, 0.0, 0.0, 0.75);
+
+            // director timers and physics only run while the game is running.
+            // the running state is replaced instead of pushed over, such that nothing is left of it when the level is left
+            pause.paused_the_game = game_state.current() == &GameState::Running;
+            if pause.paused_the_game {
+                if let Err(err) = game_state.set(GameState::Paused) {
+                    warn!("could not pause the game: {:?}", err);
+                }
+            }
+
+            mixer.paused = true;
+            if let Err(err) = app_state.push(AppState::Paused) {
+                warn!("could not open the pause menu: {:?}", err);
+            }
+        }
+        AppState::Paused => {
+            resume(&pause, &mut hud, &mut app_state, &mut game_state, &mut mixer);
+        }
+        _ => {}
+    }
+}
+
+fn pause_ui(egui_context:Res<EguiContext>,
+    pause:Res<Pause>,
+    mut director:ResMut<Director>,
+    mut hud:ResMut<Hud>,
+    mut app_state:ResMut<State<AppState>>,
+    mut game_state:ResMut<State<GameState>>,
+    mut mixer:ResMut<AudioMixer>,
+    mut music:EventWriter<MusicEvent>,
+    mut exit:EventWriter<AppExit>) {
+    let (mut resumed, mut restart, mut options, mut menu, mut quit) = (false, false, false, false, false);
+    egui::Window::new("Paused")
+    .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+    .collapsible(false)
+    .resizable(false)
+    .show(egui_context.ctx(), |ui| {
+        ui.vertical_centered_justified(|ui| {
+            resumed = ui.button("Resume").clicked();
+            restart = ui.button("Restart Level").clicked();
+            options = ui.button("Options").clicked();
+            menu = ui.button("Quit to Menu").clicked();
+
+            // browsers do not let a page close itself
+            if cfg!(not(target_arch = "wasm32")) {
+                quit = ui.button("Quit").clicked();
+            }
+        });
+    });
+
+    if resumed {
+        resume(&pause, &mut hud, &mut app_state, &mut game_state, &mut mixer);
+    } else if restart {
+        // the game is already paused, the director runs it again once the level has been loaded
+        mixer.paused = false;
+        hud.pop();
+        director.restart();
+        if let Err(err) = app_state.pop() {
+            warn!("could not close the pause menu: {:?}", err);
+        }
+    } else if options {
+        if let Err(err) = app_state.push(AppState::Options) {
+            warn!("could not open the options: {:?}", err);
+        }
+    } else if menu {
+        music.send(MusicEvent::Stop);
+        mixer.paused = false;
+        hud.pop();
+        director.reset();
+        if let Err(err) = app_state.replace(AppState::MainMenu) {
+            warn!("could not return to the main menu: {:?}", err);
+        }
+    } else if quit {
+        exit.send(AppExit);
+    }
+}
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(Pause::default());
+        app.add_system_to_stage(CoreStage::PreUpdate, input.system().after(ActionSystem));
+        app.add_system_set(SystemSet::on_update(AppState::Paused).with_system(pause_ui.system()));
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+
+use crate::{LevelStats, Levels};
+
+#[cfg(not(target_arch = "wasm32"))]
+const PROFILE_FILE:&str = "profile.json";
+#[cfg(target_arch = "wasm32")]
+const PROFILE_KEY:&str = "some-tank-game.profile";
+
+// progress of a single level
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelProgress {
+    pub completed:bool,
+    // fastest time the level was won in, in seconds
+    pub best_time:Option<f32>,
+    // best rating the level was won with, from 1 to 3
+    pub stars:u8,
+    pub best_accuracy:Option<f32>,
+    pub most_kills:u32,
+    pub least_damage:Option<f32>
+}
+
+// campaign progress of the player, by map name such that reordering levels.json keeps it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub levels:HashMap<String, LevelProgress>
+}
+
+impl Profile {
+    pub fn progress(&self, map:&str) -> Option<&LevelProgress> {
+        self.levels.get(map)
+    }
+
+    // a level is unlocked once every level it requires has been completed
+    pub fn is_unlocked(&self, levels:&Levels, level:u32) -> bool {
+        match levels.get(level) {
+            Some(l) => l.requires.iter().flatten().all(|r| {
+                levels.get_map(*r).and_then(|map| self.progress(map)).map(|p| p.completed).unwrap_or(false)
+            }),
+            None => false
+        }
+    }
+
+    pub fn has_progress(&self) -> bool {
+        self.levels.values().any(|p| p.completed)
+    }
+
+    // first unlocked level that has not been completed yet, or the last level if all are
+    pub fn next_level(&self, levels:&Levels) -> u32 {
+        (1..=levels.count())
+            .find(|l| self.is_unlocked(levels, *l) && levels.get_map(*l).and_then(|m| self.progress(m)).map(|p| p.completed) != Some(true))
+            .unwrap_or(levels.count())
+    }
+
+    // records a won level, returns the progress from before such that new bests can be told apart
+    pub fn complete(&mut self, map:&str, stats:&LevelStats, stars:u8) -> LevelProgress {
+        let progress = self.levels.entry(map.into()).or_default();
+        let previous = progress.clone();
+        progress.completed = true;
+        progress.stars = progress.stars.max(stars);
+        progress.best_time = Some(progress.best_time.map(|t| t.min(stats.time)).unwrap_or(stats.time));
+        progress.best_accuracy = Some(progress.best_accuracy.map(|a| a.max(stats.accuracy())).unwrap_or(stats.accuracy()));
+        progress.most_kills = progress.most_kills.max(stats.kills);
+        progress.least_damage = Some(progress.least_damage.map(|d| d.min(stats.damage_taken)).unwrap_or(stats.damage_taken));
+        previous
+    }
+
+    fn from_json(json:&str) -> Self {
+        serde_json::from_str(json).unwrap_or_else(|err| {
+            warn!("could not read profile: {}", err);
+            Self::default()
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load() -> Self {
+        std::fs::read_to_string(PROFILE_FILE).map(|json| Self::from_json(&json)).unwrap_or_default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self) {
+        let json = serde_json::to_string_pretty(self).unwrap();
+        if let Err(err) = std::fs::write(PROFILE_FILE, json) {
+            warn!("could not save profile to {}: {}", PROFILE_FILE, err);
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn storage() -> Option<web_sys::Storage> {
+        web_sys::window().and_then(|w| w.local_storage().ok().flatten())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn load() -> Self {
+        Self::storage().and_then(|s| s.get_item(PROFILE_KEY).ok().flatten()).map(|json| Self::from_json(&json)).unwrap_or_default()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn save(&self) {
+        let json = serde_json::to_string(self).unwrap();
+        match Self::storage() {
+            Some(storage) => if storage.set_item(PROFILE_KEY, &json).is_err() {
+                warn!("could not save profile to local storage");
+            },
+            None => warn!("local storage is not available, the profile is not saved")
+        }
+    }
+}
+
+pub struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(Profile::load());
+    }
+}
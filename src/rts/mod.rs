@@ -0,0 +1,228 @@
+use bevy::{prelude::*, render::camera::Camera};
+use bevy_egui::{EguiContext, egui::{Color32, Id, LayerId, Order as LayerOrder, Pos2, Rect, Stroke}};
+
+use crate::{Action, Actions, AppState, Autopilot, Bot, Formation, GameCamera, GameState, Hud, Order, Player, Selected, Tank, Turret, Unit, WaypointEvent, follow_path, mouse::{Mouse, MouseSystem}};
+
+// distance between units in a formation
+const FORMATION_SPACING:f32 = 1.5;
+// drags shorter than this select the single unit below the cursor
+const CLICK_DISTANCE:f32 = 0.3;
+const CLICK_RADIUS:f32 = 0.6;
+
+#[derive(Default)]
+pub struct Rts {
+    pub enabled:bool,
+    pub formation:Formation,
+    // world position where the selection box was started
+    pub drag_start:Option<Vec2>
+}
+
+#[derive(Hash, Copy, Clone, PartialEq, Eq, Debug, SystemLabel)]
+pub struct RtsSystem;
+
+fn rts_input_system(mut commands:Commands,
+    mut rts:ResMut<Rts>,
+    actions:Res<Actions>,
+    mouse:Res<Mouse>,
+    mut hud:ResMut<Hud>,
+    mut units:Query<(Entity, &mut Unit, &mut Autopilot, &Transform, Option<&Selected>)>,
+    mut waypoint_event_writer:EventWriter<WaypointEvent>) {
+    if actions.just_pressed(Action::ToggleRts) {
+        rts.enabled = !rts.enabled;
+        rts.drag_start = None;
+        hud.top_right_text = if rts.enabled { format!("RTS - {:?}", rts.formation) } else { "".into() };
+        if rts.enabled == false {
+            for (e, ..) in units.iter_mut() {
+                commands.entity(e).remove::<Selected>();
+            }
+        }
+    }
+
+    if rts.enabled == false {
+        return;
+    }
+
+    if actions.just_pressed(Action::CycleFormation) {
+        rts.formation = rts.formation.next();
+        hud.top_right_text = format!("RTS - {:?}", rts.formation);
+    }
+
+    // selection
+    let mp = mouse.pos_world.truncate();
+    if actions.just_pressed(Action::Select) {
+        rts.drag_start = Some(mp);
+    } else if actions.just_released(Action::Select) {
+        if let Some(start) = rts.drag_start.take() {
+            let (min, max) = (start.min(mp), start.max(mp));
+            let click = start.distance(mp) < CLICK_DISTANCE;
+            for (e, _, _, transform, _) in units.iter_mut() {
+                let p = transform.translation.truncate();
+                let hit = if click {
+                    p.distance(mp) <= CLICK_RADIUS
+                } else {
+                    p.x >= min.x && p.x <= max.x && p.y >= min.y && p.y <= max.y
+                };
+                if hit {
+                    commands.entity(e).insert(Selected);
+                } else {
+                    commands.entity(e).remove::<Selected>();
+                }
+            }
+        }
+    }
+
+    // orders
+    let mut issued = false;
+    if actions.just_pressed(Action::HoldOrder) {
+        for (_, mut unit, mut autopilot, _, selected) in units.iter_mut() {
+            if selected.is_some() {
+                unit.order = Order::Hold;
+                autopilot.clear();
+                issued = true;
+            }
+        }
+    } else if actions.just_pressed(Action::MoveOrder) {
+        let target = mp.extend(0.0);
+        let attack = actions.pressed(Action::AttackMoveModifier);
+
+        let mut selected:Vec<(Entity, Vec2)> = units.iter_mut()
+            .filter(|(.., selected)| selected.is_some())
+            .map(|(e, _, _, transform, _)| (e, transform.translation.truncate()))
+            .collect();
+        if selected.len() > 0 {
+            let centroid = selected.iter().fold(Vec2::ZERO, |acc, (_, p)| acc + *p) / selected.len() as f32;
+            let forward = (mp - centroid).normalize_or_zero();
+            let forward = if forward == Vec2::ZERO { Vec2::new(1.0, 0.0) } else { forward };
+            let left = Vec2::new(-forward.y, forward.x);
+
+            // fill the slots front to back, each with the closest remaining unit
+            let count = selected.len();
+            for i in 0..count {
+                let offset = rts.formation.offset(i, count, FORMATION_SPACING);
+                let slot = mp + forward * offset.x + left * offset.y;
+                let closest = selected.iter().enumerate()
+                    .min_by(|a, b| (a.1).1.distance(slot).partial_cmp(&(b.1).1.distance(slot)).unwrap())
+                    .map(|(j, _)| j)
+                    .unwrap();
+                let (e, _) = selected.remove(closest);
+
+                if let Ok((_, mut unit, mut autopilot, _, _)) = units.get_mut(e) {
+                    let goal = slot.extend(target.z);
+                    unit.order = if attack { Order::AttackMove(goal) } else { Order::Move(goal) };
+                    autopilot.clear();
+                    autopilot.waypoints.push_back(goal.into());
+                    issued = true;
+                }
+            }
+        }
+    }
+
+    if issued {
+        waypoint_event_writer.send(WaypointEvent::Clear);
+        for (_, _, autopilot, ..) in units.iter_mut() {
+            for w in autopilot.waypoints.iter() {
+                waypoint_event_writer.send(WaypointEvent::Added(*w));
+            }
+        }
+    }
+}
+
+// drives units along their orders
+fn unit_system(rts:Res<Rts>,
+    mut units:Query<(&mut Unit, &mut Autopilot, &mut Tank, &Transform, &Children, Option<&Bot>, Option<&Player>)>,
+    mut turrets:Query<&mut Turret>,
+    mut waypoint_event_writer:EventWriter<WaypointEvent>) {
+    for (mut unit, mut autopilot, mut tank, transform, children, bot, player) in units.iter_mut() {
+        // outside of rts mode the player drives the tank
+        if player.is_some() && rts.enabled == false {
+            continue;
+        }
+
+        // a commanded player tank does not fire on its own
+        if player.is_some() && bot.is_none() {
+            for e in children.iter() {
+                if let Ok(mut turret) = turrets.get_component_mut::<Turret>(*e) {
+                    turret.trigger = false;
+                }
+            }
+        }
+
+        let engaging = bot.map(|b| b.sensors.get_closest_visible_enemy().is_some()).unwrap_or(false);
+        match unit.order {
+            Order::Idle => {
+                if bot.is_none() {
+                    tank.tracks = Vec2::ZERO;
+                }
+            }
+            Order::Hold => {
+                tank.tracks = Vec2::ZERO;
+            }
+            Order::Move(_) | Order::AttackMove(_) => {
+                if let (Order::AttackMove(_), true) = (unit.order, engaging) {
+                    tank.tracks = Vec2::ZERO;
+                } else {
+                    for w in follow_path(&mut tank, &mut autopilot, transform) {
+                        waypoint_event_writer.send(WaypointEvent::Removed(w));
+                    }
+                }
+
+                // arrived, bots go back to their own behaviour, only an explicit hold keeps them in place
+                if autopilot.waypoints.len() == 0 {
+                    unit.order = Order::Idle;
+                    tank.tracks = Vec2::ZERO;
+                }
+            }
+        }
+    }
+}
+
+fn world_to_screen(p:Vec3, camera:&Camera, camera_transform:&Transform, size:Vec2) -> Pos2 {
+    let ndc = camera.projection_matrix.project_point3(camera_transform.compute_matrix().inverse().transform_point3(p));
+    let screen = (ndc.truncate() + Vec2::ONE) / 2.0 * size;
+    // egui has y pointing down
+    Pos2::new(screen.x, size.y - screen.y)
+}
+
+fn rts_ui_system(egui_context:Res<EguiContext>,
+    rts:Res<Rts>,
+    mouse:Res<Mouse>,
+    windows:Res<Windows>,
+    camera:Query<(&Camera, &Transform), With<GameCamera>>,
+    selected:Query<&Transform, With<Selected>>) {
+    if rts.enabled == false {
+        return;
+    }
+
+    if let (Some(window), Ok((camera, camera_transform))) = (windows.get_primary(), camera.single()) {
+        let size = Vec2::new(window.width(), window.height());
+        let painter = egui_context.ctx().layer_painter(LayerId::new(LayerOrder::Foreground, Id::new("rts")));
+        let stroke = Stroke::new(1.5, Color32::from_rgb(0, 0xa6, 0x04));
+
+        for transform in selected.iter() {
+            let center = world_to_screen(transform.translation, camera, camera_transform, size);
+            let edge = world_to_screen(transform.translation + Vec3::new(CLICK_RADIUS, 0.0, 0.0), camera, camera_transform, size);
+            painter.circle_stroke(center, (edge.x - center.x).abs(), stroke);
+        }
+
+        if let Some(start) = rts.drag_start {
+            let a = world_to_screen(start.extend(0.0), camera, camera_transform, size);
+            let b = world_to_screen(mouse.pos_world.truncate().extend(0.0), camera, camera_transform, size);
+            painter.rect_stroke(Rect::from_two_pos(a, b), 0.0, stroke);
+        }
+    }
+}
+
+pub struct RtsPlugin;
+
+impl Plugin for RtsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(Rts::default());
+        app.add_system_set(SystemSet::on_update(AppState::InGame)
+            .with_system(rts_input_system.system().label(RtsSystem).after(MouseSystem))
+            .with_system(rts_ui_system.system())
+        );
+        app.add_system_set(SystemSet::on_update(GameState::Running)
+            .with_system(unit_system.system().after(RtsSystem))
+        );
+    }
+}
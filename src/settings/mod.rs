@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContext, egui::{self, Align2}};
+
+use crate::{Action, Actions, AppState, AudioMixer, Binding, Bindings, Config};
+
+#[derive(Default)]
+pub struct Settings {
+    pub open:bool,
+    // action waiting for a new binding
+    pub rebinding:Option<Action>,
+    // set the frame after rebinding was requested, such that the click on the button is not captured
+    armed:bool
+}
+
+fn pressed_binding(keys:&Input<KeyCode>, mouse:&Input<MouseButton>) -> Option<Binding> {
+    if let Some(key) = keys.get_just_pressed().next() {
+        return Some(Binding::Key(*key));
+    }
+
+    mouse.get_just_pressed().next().map(|b| Binding::Mouse(*b))
+}
+
+fn capture_binding(settings:&mut Settings, bindings:&mut Bindings, config:&mut Config, keys:&Input<KeyCode>, mouse:&Input<MouseButton>) {
+    if let Some(action) = settings.rebinding {
+        if settings.armed == false {
+            settings.armed = true;
+            return;
+        }
+
+        if let Some(binding) = pressed_binding(keys, mouse) {
+            // escape cancels rebinding
+            if binding != Binding::Key(KeyCode::Escape) {
+                bindings.set(action, vec![binding], config);
+                config.save();
+            }
+
+            settings.rebinding = None;
+        }
+    }
+}
+
+pub fn controls_ui(ui:&mut egui::Ui, settings:&mut Settings, bindings:&mut Bindings, config:&mut Config) {
+    egui::Grid::new("Bindings").striped(true).show(ui, |ui| {
+        for action in Action::all() {
+            ui.label(action.name());
+            if settings.rebinding == Some(action) {
+                ui.label("Press a key or button...");
+            } else {
+                let current = bindings.get(action).iter().map(|b| b.to_string()).collect::<Vec<String>>().join(", ");
+                ui.label(current);
+            }
+
+            if ui.button("Rebind").clicked() {
+                settings.rebinding = Some(action);
+                settings.armed = false;
+            }
+
+            if ui.button("Default").clicked() {
+                bindings.set(action, action.default_bindings(), config);
+                config.save();
+            }
+            ui.end_row();
+        }
+    });
+}
+
+pub fn audio_ui(ui:&mut egui::Ui, mixer:&mut AudioMixer, config:&mut Config) {
+    let (mut changed, mut done) = (false, false);
+    egui::Grid::new("Audio").show(ui, |ui| {
+        let mut volume = |ui:&mut egui::Ui, label:&str, value:&mut f32| {
+            ui.label(label);
+            let response = ui.add(egui::Slider::new(value, 0.0..=1.0));
+            changed |= response.changed();
+            // while dragging the volume is only applied, it is saved once the slider is let go
+            done |= response.drag_released() || (response.changed() && response.dragged() == false);
+            ui.end_row();
+        };
+        volume(ui, "Master", &mut mixer.master);
+        volume(ui, "Music", &mut mixer.music);
+        volume(ui, "Effects", &mut mixer.sfx);
+        volume(ui, "Interface", &mut mixer.ui);
+    });
+
+    if changed {
+        config.set_audio_mixer(mixer);
+    }
+    if done {
+        config.save();
+    }
+}
+
+fn settings_system(egui_context:Res<EguiContext>, mut settings:ResMut<Settings>, mut bindings:ResMut<Bindings>, mut config:ResMut<Config>, mut mixer:ResMut<AudioMixer>, actions:Res<Actions>, keys:Res<Input<KeyCode>>, mouse:Res<Input<MouseButton>>, app_state:Res<State<AppState>>) {
+    capture_binding(&mut settings, &mut bindings, &mut config, &keys, &mouse);
+
+    if settings.rebinding.is_none() && actions.just_pressed(Action::ToggleSettings) {
+        settings.open = !settings.open;
+    }
+
+    if settings.open == false {
+        // the options screen rebinds through the same settings
+        if app_state.current() != &AppState::Options {
+            settings.rebinding = None;
+        }
+        return;
+    }
+
+    let mut open = settings.open;
+    egui::Window::new("Settings")
+    .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+    .collapsible(false)
+    .resizable(false)
+    .open(&mut open)
+    .show(egui_context.ctx(), |ui| {
+        ui.heading("Audio");
+        // only touch the mixer when a volume changes, such that the channels are not updated every frame
+        let mut volumes = *mixer;
+        audio_ui(ui, &mut volumes, &mut config);
+        if volumes.master != mixer.master || volumes.music != mixer.music || volumes.sfx != mixer.sfx || volumes.ui != mixer.ui {
+            *mixer = volumes;
+        }
+
+        ui.separator();
+        ui.heading("Controls");
+        controls_ui(ui, &mut settings, &mut bindings, &mut config);
+    });
+    settings.open = open;
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(Settings::default());
+        app.add_system(settings_system.system());
+    }
+}
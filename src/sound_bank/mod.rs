@@ -0,0 +1,54 @@
+use bevy::{asset::LoadState, prelude::*};
+
+use crate::{AssetCache, Json, SoundBank};
+
+#[derive(Default)]
+struct SoundBankState {
+    ready:bool
+}
+
+// loads sounds.json into the sound bank and preloads every variation
+fn load_sound_bank(mut state:Local<SoundBankState>, mut bank:ResMut<SoundBank>, asset_server:Res<AssetServer>, json:Res<Assets<Json>>, mut asset_cache:ResMut<AssetCache>, mut events:EventReader<AssetEvent<Json>>) {
+    // sounds.json changed on disk, read it again
+    for e in events.iter() {
+        if let AssetEvent::Modified { handle } = e {
+            if *handle == asset_server.get_handle("sounds.json") {
+                state.ready = false;
+            }
+        }
+    }
+
+    if state.ready {
+        return;
+    }
+
+    let handle:Handle<Json> = asset_server.get_handle("sounds.json");
+    if asset_cache.contains(&handle) == false {
+        let handle:Handle<Json> = asset_server.load("sounds.json");
+        asset_cache.track(&handle);
+    } else if asset_cache.is_loaded(&handle) {
+        let json = json.get(handle).unwrap();
+        match serde_json::from_value::<SoundBank>(json.0.clone()) {
+            Ok(loaded) => {
+                for path in loaded.paths() {
+                    asset_cache.track_untyped(&asset_server.load_untyped(path.as_str()));
+                }
+                *bank = loaded;
+            }
+            Err(err) => warn!("could not parse sounds.json: {}", err)
+        }
+        state.ready = true;
+    } else if asset_server.get_load_state(&handle) == LoadState::Failed {
+        // named sounds stay silent without the bank
+        warn!("could not load sounds.json");
+        state.ready = true;
+    }
+}
+
+pub struct SoundBankPlugin;
+
+impl Plugin for SoundBankPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(load_sound_bank.system());
+    }
+}
@@ -1,6 +1,6 @@
 use bevy::{prelude::*};
 
-use crate::{AppState, AssetCache, Config, DelayState, Hud};
+use crate::{Action, Actions, AppState, AssetCache, AssetManifest, Config, DelayState, Hud};
 
 pub struct SplashPlugin;
 
@@ -8,18 +8,19 @@ fn hide_splash(mut hud:ResMut<Hud>) {
     hud.clear_texts();
 }
 
-fn update(config:Res<Config>, mouse_input:Res<Input<MouseButton>>, mut app_state:ResMut<DelayState<AppState>>, mut hud:ResMut<Hud>, asset_cache:Res<AssetCache>) {
+fn update(config:Res<Config>, actions:Res<Actions>, mut app_state:ResMut<DelayState<AppState>>, mut hud:ResMut<Hud>, asset_cache:Res<AssetCache>, manifest:Res<AssetManifest>) {
     
     hud.top_right_text = format!("Build Date\n{}", env!("BUILD_DATE"));
     hud.top_left_text = format!("Version\nV{} ({})", env!("CARGO_PKG_VERSION"), env!("GIT_HASH"));
     hud.center_text = "Some Tank Game!".into();
 
-    if asset_cache.all_is_loaded() {
-        hud.bottom_center_text = "Use W,A,S,D to drive your tank.\nUse the mouse to point and shoot!".into();
+    // nothing is tracked until the manifest has been read
+    if manifest.ready && asset_cache.all_is_loaded() {
+        hud.bottom_center_text = "Use W,A,S,D to drive your tank.\nUse the mouse to point and shoot!\nPress F5 in game for a random skirmish.".into();
         hud.bottom_left_text = "Music by Zander Noriega".into();
-        if mouse_input.just_pressed(MouseButton::Left) && asset_cache.all_is_loaded() {
+        if actions.just_pressed(Action::Confirm) && asset_cache.all_is_loaded() {
             let time = if config.quick() == false { 0.5 } else { 0.0 };
-            app_state.set(AppState::InGame, time);
+            app_state.set(AppState::MainMenu, time);
     
             if config.quick() == false {
                 hud.fade(time, time, Color::BLACK);
@@ -28,6 +29,12 @@ fn update(config:Res<Config>, mouse_input:Res<Input<MouseButton>>, mut app_state
     } else {
         hud.center_text = format!("Loading {}/{}", asset_cache.loaded_count(), asset_cache.count());
     }
+
+    // missing assets are listed, the game can still be started without them
+    if asset_cache.has_failed() {
+        let failed:Vec<String> = asset_cache.failed().iter().map(|f| format!("{}: {}", f.path, f.error)).collect();
+        hud.center_text = format!("{}\n\nFailed to load {} asset(s)\n{}", hud.center_text, failed.len(), failed.join("\n"));
+    }
 }
 
 impl Plugin for SplashPlugin {
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+
+use crate::{ApplyDamageEvent, DeathEvent, GameState, Health, Owner, Player, ProjectileHitEvent, TurretFireEvent};
+
+// what the player did in a level
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelStats {
+    pub shots:u32,
+    // shots that hit something with health
+    pub hits:u32,
+    pub kills:u32,
+    pub damage_taken:f32,
+    // seconds it took to win the level
+    pub time:f32
+}
+
+impl LevelStats {
+    // hits per shot, between 0.0 and 1.0
+    pub fn accuracy(&self) -> f32 {
+        if self.shots == 0 {
+            return 0.0;
+        }
+
+        (self.hits as f32 / self.shots as f32).min(1.0)
+    }
+
+    pub fn add(&mut self, other:&LevelStats) {
+        self.shots += other.shots;
+        self.hits += other.hits;
+        self.kills += other.kills;
+        self.damage_taken += other.damage_taken;
+        self.time += other.time;
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Stats {
+    // the level being played
+    pub level:LevelStats,
+    // every level won since the game was started
+    pub game:LevelStats
+}
+
+impl Stats {
+    pub fn start_level(&mut self) {
+        self.level = LevelStats::default();
+    }
+
+    pub fn finish_level(&mut self, time:f32) {
+        self.level.time = time;
+        let level = self.level;
+        self.game.add(&level);
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+// runs after projectiles and health, such that projectiles and their targets are not yet despawned
+fn stats_system(mut stats:ResMut<Stats>,
+    players:Query<Entity, With<Player>>,
+    owners:Query<&Owner>,
+    healths:Query<&Health>,
+    mut fired:EventReader<TurretFireEvent>,
+    mut hits:EventReader<ProjectileHitEvent>,
+    mut damage:EventReader<ApplyDamageEvent>,
+    mut deaths:EventReader<DeathEvent>) {
+    let player = players.iter().next();
+    let is_player = |e:Entity| Some(e) == player;
+
+    for e in fired.iter() {
+        if is_player(e.tank) {
+            stats.level.shots += 1;
+        }
+    }
+
+    for e in hits.iter() {
+        let owner = owners.get(e.projectile).map(|o| o.owner).ok();
+        if owner.map(is_player).unwrap_or(false) && is_player(e.target) == false && healths.get(e.target).is_ok() {
+            stats.level.hits += 1;
+        }
+    }
+
+    for e in damage.iter() {
+        if is_player(e.target) {
+            stats.level.damage_taken += e.amount;
+        }
+    }
+
+    for e in deaths.iter() {
+        if e.killer.map(is_player).unwrap_or(false) && is_player(e.entity) == false {
+            stats.level.kills += 1;
+        }
+    }
+}
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(Stats::default());
+        app.add_system_set(SystemSet::on_update(GameState::Running)
+            .with_system(stats_system.system().after("projectile").after("health"))
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+
+use crate::{Autopilot, Tank, Waypoint};
+
+// the slowest the autopilot drives while approaching the goal
+const MIN_SPEED:f32 = 0.25;
+// how strongly the curvature of the pursuit arc turns into a track difference
+const TURN_GAIN:f32 = 0.6;
+// targets behind the tank closer than this are reached by reversing
+const REVERSE_DISTANCE:f32 = 2.5;
+
+// steers the tank along the waypoints using pure pursuit, returns the waypoints which have been passed
+pub fn follow_path(tank:&mut Tank, autopilot:&mut Autopilot, transform:&Transform) -> Vec<Waypoint> {
+    let mut passed = Vec::new();
+    let p = transform.translation;
+
+    // drop waypoints inside the lookahead, except the last one which must be reached
+    while autopilot.waypoints.len() > 1 && autopilot.waypoints[0].location.distance(p) < autopilot.lookahead {
+        if let Some(w) = autopilot.waypoints.pop_front() {
+            passed.push(w);
+        }
+    }
+
+    let target = match autopilot.waypoints.front() {
+        Some(w) => w.location,
+        None => {
+            tank.tracks = Vec2::ZERO;
+            return passed;
+        }
+    };
+
+    let remaining = autopilot.remaining_distance(p);
+    if autopilot.waypoints.len() == 1 && remaining <= autopilot.goal_radius {
+        if let Some(w) = autopilot.waypoints.pop_front() {
+            passed.push(w);
+        }
+        autopilot.reversing = false;
+        tank.tracks = Vec2::ZERO;
+        return passed;
+    }
+
+    // target in the local frame of the tank, x is forward and y is left
+    let local = (transform.rotation.inverse() * (target - p)).truncate();
+    let l = local.length().max(0.001);
+
+    // reverse when the target is close and behind, with some hysteresis to avoid flipping back and forth
+    autopilot.reversing = if autopilot.allow_reverse && remaining < REVERSE_DISTANCE {
+        if autopilot.reversing { local.x < 0.2 * l } else { local.x < -0.5 * l }
+    } else {
+        false
+    };
+
+    // when reversing the rear becomes the front, which mirrors both axes
+    let (direction, local) = if autopilot.reversing { (-1.0, -local) } else { (1.0, local) };
+
+    let curvature = 2.0 * local.y / (l * l);
+    let turn = if local.x < 0.0 {
+        // pivot towards targets behind, the arc would be too shallow to turn around
+        if local.y < 0.0 { -1.0 } else { 1.0 }
+    } else {
+        (curvature * TURN_GAIN).max(-1.0).min(1.0)
+    };
+
+    // slow down near the goal and while facing away from the target
+    let alignment = (local.x / l).max(0.0);
+    let speed = (remaining / autopilot.slow_radius).max(MIN_SPEED).min(1.0) * alignment;
+    let throttle = direction * speed;
+
+    let mut tracks = Vec2::new(throttle + turn, throttle - turn);
+    let max = tracks.x.abs().max(tracks.y.abs());
+    if max > 1.0 {
+        tracks /= max;
+    }
+    tank.tracks = tracks;
+
+    passed
+}
@@ -1,11 +1,32 @@
 use std::f32::consts::PI;
 
 use bevy::prelude::*;
+use rand::random;
 use bevy_rapier2d::{physics::{RigidBodyHandleComponent}, rapier::{dynamics::{RigidBodySet}, geometry::{ColliderSet, InteractionGroups, Ray}, math::Real, pipeline::QueryPipeline}};
 
-use crate::{Bot, BotState, Enemy, Faction, Tank, Turret};
+use crate::{BULLET_SPEED, Behaviour, Bot, BotState, BotTuning, Enemy, Faction, NoiseEvent, Order, Tank, TeamIntel, Turret, Unit, lead_target};
+
+// seconds intel shared between allies stays valid
+const INTEL_MEMORY:f32 = 3.0;
+
+pub fn bot_sensor_system(time:Res<Time>,
+    mut intel:ResMut<TeamIntel>,
+    mut noise_reader:EventReader<NoiseEvent>,
+    tanks:Query<(Entity, &Tank, &Faction)>,
+    bots:Query<(Entity, &mut Bot, &Faction)>,
+    rigid_bodies:Query<&RigidBodyHandleComponent>,
+    rigid_body_set:Res<RigidBodySet>,
+    collider_set:Res<ColliderSet>,
+    query_pipeline: Res<QueryPipeline>) {
+    let now = time.time_since_startup().as_secs_f64();
+    let noises:Vec<NoiseEvent> = noise_reader.iter().copied().collect();
+
+    // intel on enemies which have been destroyed is useless
+    intel.forget(now, INTEL_MEMORY);
+    for enemies in intel.enemies.values_mut() {
+        enemies.retain(|e, _| tanks.get(*e).is_ok());
+    }
 
-pub fn bot_sensor_system(tanks:Query<(Entity, &Tank, &Faction)>, bots:Query<(Entity, &mut Bot, &Faction)>, rigid_bodies:Query<&RigidBodyHandleComponent>, rigid_body_set:Res<RigidBodySet>, collider_set:Res<ColliderSet>, query_pipeline: Res<QueryPipeline>) {
     bots.for_each_mut(|(bot_entity, mut bot, my_faction)| {
         if let Ok(bot_body) = rigid_bodies.get_component::<RigidBodyHandleComponent>(bot_entity) {
             if let Some(bot_body) = rigid_body_set.get(bot_body.handle()) {
@@ -14,67 +35,153 @@ pub fn bot_sensor_system(tanks:Query<(Entity, &Tank, &Faction)>, bots:Query<(Ent
                 bot.sensors.obstacle_distance_left = raycast_front_distance(bot_body, &query_pipeline, &collider_set, PI / 2.0);
                 bot.sensors.obstacle_distance_right = raycast_front_distance(bot_body, &query_pipeline, &collider_set, -PI / 2.0);
 
-                // collect know enemies
+                let perception = bot.perception;
+                let my_pos:Vec3 = [bot_body.position().translation.x, bot_body.position().translation.y, 0.0].into();
+                let forward:Vec3 = [bot_body.position().rotation.re, bot_body.position().rotation.im, 0.0].into();
+
+                // see enemies inside the view cone, or very close by, with a clear line of sight
+                bot.sensors.visible_enemies.clear();
                 tanks.for_each(|(tank_entity, _tank, faction)| {
-                    if tank_entity != bot_entity && my_faction != faction {
-                        if let Ok(enemy_body) = rigid_bodies.get_component::<RigidBodyHandleComponent>(tank_entity) {
-                            if let Some(enemy_body) = rigid_body_set.get(enemy_body.handle()) {
-                                let my_pos:Vec3 = [bot_body.position().translation.x, bot_body.position().translation.y, 0.0].into();
-                                let pos:Vec3 = [enemy_body.position().translation.x, enemy_body.position().translation.y, 0.0].into();
-                                
-                                bot.sensors.known_enemies.push(Enemy {
-                                    entity:tank_entity,
-                                    position:pos,
-                                    distance: my_pos.distance(pos)
-                                });
+                    if tank_entity == bot_entity || my_faction == faction {
+                        return;
+                    }
+
+                    if let Ok(enemy_body) = rigid_bodies.get_component::<RigidBodyHandleComponent>(tank_entity) {
+                        if let Some(enemy_body) = rigid_body_set.get(enemy_body.handle()) {
+                            let pos:Vec3 = [enemy_body.position().translation.x, enemy_body.position().translation.y, 0.0].into();
+                            let distance = my_pos.distance(pos);
+                            let in_view = distance <= perception.view_range && forward.angle_between(pos - my_pos) <= perception.view_angle;
+                            if in_view == false && distance > perception.awareness_range {
+                                return;
+                            }
+
+                            if let Some((handle, _)) = raycast_target(pos.truncate(), bot_body, &query_pipeline, &collider_set) {
+                                if let Some(collider) = collider_set.get(handle) {
+                                    if Entity::from_bits(collider.user_data as u64) == tank_entity {
+                                        let enemy = Enemy {
+                                            entity:tank_entity,
+                                            position:pos,
+                                            velocity:[enemy_body.linvel().x, enemy_body.linvel().y, 0.0].into(),
+                                            distance,
+                                            last_seen:now
+                                        };
+                                        bot.sensors.visible_enemies.push(enemy);
+                                        bot.sensors.remember(enemy);
+                                        intel.report(*my_faction, enemy);
+                                    }
+                                }
                             }
                         }
                     }
                 });
 
-                // find visibile enemies
-                bot.sensors.visible_enemies.clear();
-                let sensors = &mut bot.sensors;
-                let known_enemies = &sensors.known_enemies;
-                let visible_enemies = &mut sensors.visible_enemies;
-                for enemy in known_enemies {
-                    let test = raycast_target(enemy.position.truncate(), bot_body, &query_pipeline, &collider_set);
-                    if let Some((handle, _)) = test {
-                        if let Some(collider) = collider_set.get(handle) {
-                            let e = Entity::from_bits(collider.user_data as u64);
-                            if enemy.entity == e {
-                                visible_enemies.push(*enemy);
+                // hear gunshots and explosions, enemies firing give away their position
+                for noise in noises.iter() {
+                    if my_pos.distance(noise.position) > noise.range * perception.hearing {
+                        continue;
+                    }
+
+                    bot.sensors.last_noise = Some((noise.position, now));
+                    if let Some(source) = noise.source {
+                        if let Ok((_, _, faction)) = tanks.get(source) {
+                            if faction != my_faction {
+                                bot.sensors.remember(Enemy {
+                                    entity:source,
+                                    position:noise.position,
+                                    velocity:Vec3::ZERO,
+                                    distance:my_pos.distance(noise.position),
+                                    last_seen:now
+                                });
                             }
                         }
                     }
                 }
+
+                // learn about enemies spotted by allies
+                for enemy in intel.known(*my_faction) {
+                    bot.sensors.remember(enemy);
+                }
+
+                // forget enemies and noises not noticed for a while, or destroyed
+                bot.sensors.forget(now, perception.memory);
+                if bot.sensors.last_noise.map(|(_, t)| now - t > perception.memory as f64).unwrap_or(false) {
+                    bot.sensors.last_noise = None;
+                }
+                bot.sensors.known_enemies.retain(|e| tanks.get(e.entity).is_ok());
+                for enemy in bot.sensors.known_enemies.iter_mut() {
+                    enemy.distance = my_pos.distance(enemy.position);
+                }
             }
         }
     });
 }
 
 
-pub fn bot_system(mut turrets:Query<(Entity, &mut Turret)>, bots:Query<(Entity, &mut Bot, &mut Tank, &RigidBodyHandleComponent)>, time:Res<Time>, bodies:Res<RigidBodySet>) {
-    bots.for_each_mut(|(_bot_entity, mut bot, mut tank, body)| {
+pub fn bot_system(mut turrets:Query<(Entity, &mut Turret)>, bots:Query<(Entity, &mut Bot, &BotTuning, &mut Tank, &RigidBodyHandleComponent, Option<&Unit>)>, time:Res<Time>, bodies:Res<RigidBodySet>) {
+    bots.for_each_mut(|(_bot_entity, mut bot, tuning, mut tank, body, unit)| {
         let t = time.time_since_startup().as_secs_f64();
-        if let Some(_body) = bodies.get(body.handle()) {
+        if let Some(body) = bodies.get(body.handle()) {
                 if bot.next_think <= t {
-                    bot.next_think = t + 0.1;
+                    bot.next_think = t + tuning.think_interval as f64;
+                    let p:Vec3 = [body.position().translation.x, body.position().translation.y, 0.0].into();
+                    let forward:Vec3 = [body.position().rotation.re, body.position().rotation.im, 0.0].into();
+                    let enemy = bot.sensors.get_closest_visible_enemy();
 
                     if let Ok(mut turret) = turrets.get_component_mut::<Turret>(tank.turret_entity) {
-                        if let Some(enemy)  = bot.sensors.get_closest_visible_enemy() {
-                            turret.target = enemy.position;
-                            if bot.trigger_timer <= 0.0 {
+                        if let Some(enemy) = enemy {
+                            // aim where the enemy will be when the bullet arrives, give or take the aim error
+                            let error = Quat::from_rotation_z((random::<f32>() * 2.0 - 1.0) * tuning.aim_error);
+                            let lead = lead_target(p, enemy.position, enemy.velocity, BULLET_SPEED);
+                            turret.target = p + error * (lead - p);
+
+                            if turret.trigger && turret.cooldown > 0.0 {
+                                // fired since the last think, hold fire for a moment
+                                turret.trigger = false;
+                                bot.trigger_timer = turret.cooldown + tuning.fire_pause;
+                            } else if bot.trigger_timer <= 0.0 && enemy.distance <= tuning.engagement_range {
                                 turret.trigger = true;
                             }
                         } else {
                             turret.trigger = false;
-                            bot.trigger_timer = 15.0;
+                            bot.trigger_timer = tuning.reaction_time;
+
+                            // keep the turret pointed at the last known enemy or noise
+                            if let Some(enemy) = bot.sensors.get_most_recent_known_enemy() {
+                                turret.target = enemy.position;
+                            } else if let Some((position, _)) = bot.sensors.last_noise {
+                                turret.target = position;
+                            }
                         }
                     }
-                    bot.attack_timer -= 1.0;
-                    bot.trigger_timer -= 1.0;
+                    bot.attack_timer -= tuning.think_interval;
+                    bot.trigger_timer -= tuning.think_interval;
+
+                    // units with orders are driven by their orders
+                    if unit.map(|u| u.order != Order::Idle).unwrap_or(false) {
+                        return;
+                    }
 
+                    // personalities react differently to visible enemies
+                    if let Some(enemy) = enemy {
+                        let to_enemy = enemy.position - p;
+                        match tuning.behaviour {
+                            Behaviour::Aggressive if bot.sensors.obstacle_distance_front >= tuning.obstacle_front => {
+                                tank.tracks = if enemy.distance > tuning.engagement_range * 0.5 { drive_towards(forward, to_enemy) } else { Vec2::ZERO };
+                                return;
+                            }
+                            Behaviour::Sniper => {
+                                tank.tracks = Vec2::ZERO;
+                                return;
+                            }
+                            Behaviour::Coward if enemy.distance < tuning.engagement_range * 0.6 => {
+                                // back off while keeping the front towards the enemy, only the throttle is reversed
+                                let tracks = drive_towards(-forward, -to_enemy);
+                                tank.tracks = Vec2::new(-tracks.y, -tracks.x);
+                                return;
+                            }
+                            _ => {}
+                        }
+                    }
 
                     match bot.state {
                         BotState::Idle => {
@@ -101,8 +208,8 @@ pub fn bot_system(mut turrets:Query<(Entity, &mut Turret)>, bots:Query<(Entity,
                         }
                         BotState::Exploring => {
                             tank.tracks = [1.0, 1.0].into();
-                            let front = 1.5;
-                            let sides = 0.1;
+                            let front = tuning.obstacle_front;
+                            let sides = tuning.obstacle_side;
                             if bot.sensors.obstacle_distance_front < front || bot.sensors.obstacle_distance_left < sides || bot.sensors.obstacle_distance_right < sides {
                                 bot.state = BotState::RandomRotate;
                                 bot.mem[0] = 0.0;
@@ -128,6 +235,16 @@ pub fn bot_system(mut turrets:Query<(Entity, &mut Turret)>, bots:Query<(Entity,
     });
 }
 
+// track throttles to drive in the direction dir, turning on the spot if it is far off
+fn drive_towards(forward:Vec3, dir:Vec3) -> Vec2 {
+    let f = forward.truncate();
+    let d = dir.truncate().normalize_or_zero();
+    let angle = (f.x * d.y - f.y * d.x).atan2(f.dot(d));
+    let turn = (angle / (PI / 4.0)).max(-1.0).min(1.0);
+    let throttle = angle.cos().max(0.0);
+    Vec2::new((throttle + turn).max(-1.0).min(1.0), (throttle - turn).max(-1.0).min(1.0))
+}
+
 fn raycast_front_distance(body: &bevy_rapier2d::rapier::dynamics::RigidBody, query_pipeline: &Res<QueryPipeline>, collider_set: &Res<ColliderSet>, angle:f32) -> f32 {
     let o:Vec2 = [body.position().translation.x, body.position().translation.y].into();
     let dir:Vec2 = [body.position().rotation.re, body.position().rotation.im].into();
@@ -1,13 +1,16 @@
 use bevy::{prelude::*, render::camera::{Camera, CameraProjection, OrthographicProjection}};
-use crate::{GameCamera, Tilemap};
+use crate::{GameCamera, Player, Tilemap};
 
-pub fn camera_system(mut camera:Query<(&mut OrthographicProjection, &mut Camera, &mut Transform, &GameCamera)>, tilemap:Query<&Tilemap>,  windows: ResMut<Windows>) {
+// maximum number of tiles shown along each axis, larger maps makes the camera follow the player
+const MAX_VIEW_SIZE:usize = 32;
+
+pub fn camera_system(mut camera:Query<(&mut OrthographicProjection, &mut Camera, &mut Transform, &GameCamera)>, tilemap:Query<&Tilemap>, player:Query<&Transform, (With<Player>, Without<GameCamera>)>, windows: ResMut<Windows>) {
     let camera = camera.single_mut();
     let tilemap = tilemap.single();
     let primary = windows.get_primary();
     match (camera, tilemap, primary) {
         (Ok(camera), Ok(tilemap), Some(primary)) => {
-            let (mut projection, mut camera, mut transform, _) = camera;
+            let (mut projection, mut camera, mut transform, game_camera) = camera;
             projection.scaling_mode = bevy::render::camera::ScalingMode::None;
 
             // calculate pixel perfect integer scaling
@@ -15,7 +18,9 @@ pub fn camera_system(mut camera:Query<(&mut OrthographicProjection, &mut Camera,
             let area_width = primary.width() as u32;
             let area_height = primary.height() as u32;
 
-            let size = tilemap.size();
+            let view_width = tilemap.width().min(MAX_VIEW_SIZE);
+            let view_height = tilemap.height().min(MAX_VIEW_SIZE);
+            let size = view_width.max(view_height);
             let tilemap_width = size as u32;
             let tilemap_height = size as u32;
             let tilemap_width_px = (size * tile_size) as u32;
@@ -24,16 +29,35 @@ pub fn camera_system(mut camera:Query<(&mut OrthographicProjection, &mut Camera,
             let tilemap_integer_size = integer_scaling::calculate_size(area_width, area_height, tilemap_width_px, tilemap_height_px);
             projection.right = tilemap_width as f32 * area_width as f32 / tilemap_integer_size.width as f32;
             projection.top = tilemap_height as f32 * area_height as f32 / tilemap_integer_size.height as f32;
-            
+
             // shift optimum projection
             projection.left = -projection.right / 2.0;
             projection.bottom = -projection.top / 2.0;
             projection.right /= 2.0;
             projection.top /= 2.0;
 
-            // move the camera to the center of the tilemap
-            transform.translation.x = size as f32 / 2.0;
-            transform.translation.y = size as f32 / 2.0;
+            // zooming shrinks the visible area around the center
+            let zoom = game_camera.zoom.max(GameCamera::MIN_ZOOM);
+            projection.left /= zoom;
+            projection.right /= zoom;
+            projection.bottom /= zoom;
+            projection.top /= zoom;
+            let view_width = view_width as f32 / zoom;
+            let view_height = view_height as f32 / zoom;
+
+            // move the camera to the center of the tilemap or follow the player if the map does not fit
+            let mut center = Vec2::new(tilemap.width() as f32 / 2.0, tilemap.height() as f32 / 2.0);
+            if let Ok(player) = player.single() {
+                if tilemap.width() as f32 > view_width {
+                    center.x = player.translation.x.max(view_width / 2.0).min(tilemap.width() as f32 - view_width / 2.0);
+                }
+                if tilemap.height() as f32 > view_height {
+                    center.y = player.translation.y.max(view_height / 2.0).min(tilemap.height() as f32 - view_height / 2.0);
+                }
+            }
+
+            transform.translation.x = center.x;
+            transform.translation.y = center.y;
 
             // force update projection matrix without resize
             projection.update(primary.width(), primary.height());
@@ -41,4 +65,4 @@ pub fn camera_system(mut camera:Query<(&mut OrthographicProjection, &mut Camera,
         },
         _ =>{}
     }
-}
\ No newline at end of file
+}
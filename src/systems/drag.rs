@@ -1,7 +1,9 @@
 use bevy::prelude::*;
 use bevy_rapier2d::{physics::RigidBodyHandleComponent, rapier::{dynamics::{RigidBodySet}}};
 
-use crate::{Drag};
+use crate::{Drag, Tilemap};
+
+use super::terrain_modifiers_at;
 
 type Dragable<'a> = (&'a Drag, &'a mut RigidBodyHandleComponent);
 fn project(a:Vec2, b:Vec2) -> Vec2 {
@@ -9,7 +11,7 @@ fn project(a:Vec2, b:Vec2) -> Vec2 {
     return a1;
 }
 
-pub fn drag_system(dragable:Query<Dragable>, mut rigid_body_set:ResMut<RigidBodySet>, time:Res<Time>) {
+pub fn drag_system(dragable:Query<Dragable>, mut rigid_body_set:ResMut<RigidBodySet>, time:Res<Time>, tilemap:Query<&Tilemap>) {
     dragable.for_each_mut(|(drag, rigid_body)| {
         if let Some(rigid_body) = rigid_body_set.get_mut(rigid_body.handle()) {
             let rotation = rigid_body.position().rotation;
@@ -17,9 +19,11 @@ pub fn drag_system(dragable:Query<Dragable>, mut rigid_body_set:ResMut<RigidBody
             let side = front.perp();
 
             let v:Vec2 = [rigid_body.linvel().x, rigid_body.linvel().y].into();
+            let p:Vec2 = [rigid_body.position().translation.x, rigid_body.position().translation.y].into();
+            let terrain = terrain_modifiers_at(&tilemap, p);
 
-            let drag_front = -project(v, front) * time.delta_seconds() * drag.front;
-            let drag_side = -project(v, side) * time.delta_seconds() * drag.side;
+            let drag_front = -project(v, front) * time.delta_seconds() * drag.front * terrain.drag_front;
+            let drag_side = -project(v, side) * time.delta_seconds() * drag.side * terrain.drag_side;
 
             rigid_body.apply_impulse([drag_front.x, drag_front.y].into(), true);
             rigid_body.apply_impulse([drag_side.x, drag_side.y].into(), true);
@@ -1,13 +1,24 @@
 use bevy::prelude::*;
 
-use crate::{ApplyDamageEvent, EffectType, Health, ThingBuilder, ThingType};
+use crate::{ApplyDamageEvent, DeathEvent, EffectType, Health, NoiseEvent, ThingBuilder, ThingType};
 
-pub fn health_system(mut commands:Commands, mut health:Query<(Entity, &mut Health, &Transform)>, mut apply_damage_reader:EventReader<ApplyDamageEvent>) {
+pub fn health_system(mut commands:Commands, mut health:Query<(Entity, &mut Health, &Transform)>, mut apply_damage_reader:EventReader<ApplyDamageEvent>, mut noise:EventWriter<NoiseEvent>, mut deaths:EventWriter<DeathEvent>) {
     for e in apply_damage_reader.iter() {
         if let Ok((entity, mut health, transform)) = health.get_mut(e.target) {
+            // already dead, waiting to be despawned
+            if health.amount <= 0.0 {
+                continue;
+            }
+
             health.amount -= e.amount;
             if health.amount <= 0.0 {
                 commands.entity(entity).despawn_recursive();
+                deaths.send(DeathEvent {
+                    entity,
+                    killer:e.source,
+                    position:transform.translation
+                });
+                noise.send(NoiseEvent::explosion(transform.translation));
 
                 commands.spawn().insert(ThingBuilder {
                     translation:transform.translation,
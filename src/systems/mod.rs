@@ -27,4 +27,10 @@ mod effect;
 pub use effect::*;
 
 mod faction;
-pub use faction::*;
\ No newline at end of file
+pub use faction::*;
+
+mod terrain;
+pub use terrain::*;
+
+mod autopilot;
+pub use autopilot::*;
\ No newline at end of file
@@ -1,5 +1,4 @@
 use bevy::prelude::*;
-use rand::random;
 
 
 use crate::{ApplyDamageEvent, EffectType, Owner, PlayAudioEvent, ProjectileHitEvent, ThingBuilder, ThingType};
@@ -12,12 +11,13 @@ pub fn projectile_system(mut play_audio:EventWriter<PlayAudioEvent>, mut command
                 projectile.despawn_recursive();
                 apply_damage_writer.send(ApplyDamageEvent {
                     amount:100.0,
-                    target:hit_event.target
+                    target:hit_event.target,
+                    source:Some(owner.owner)
                 });
                 
                 let mut e = commands.spawn();
 
-                play_audio.send(format!("sfx/boom_{}.ogg", 1 + random::<u8>() % 3).into());
+                play_audio.send(PlayAudioEvent::new("explosion.small").with_position(hit_event.location));
                 e.insert(ThingBuilder {
                     translation:hit_event.location,
                     thing_type:ThingType::Effect(EffectType::BulletHit),
@@ -1,24 +1,60 @@
 use bevy::prelude::*;
 use bevy_rapier2d::{physics::RigidBodyHandleComponent, rapier::dynamics::RigidBodySet};
 
-use crate::Tank;
+use crate::{EffectType, Tank, ThingBuilder, ThingType, Tilemap};
 
-pub fn tank_system(tank:Query<(&mut Tank, &RigidBodyHandleComponent, Entity)>, mut rigid_body_set:ResMut<RigidBodySet>, time:Res<Time>, mut sprites:Query<&mut TextureAtlasSprite>) {
+use super::terrain_modifiers_at;
+
+// resistance of the tracks against turning when they fully grip
+const TRACK_GRIP:f32 = 3.75;
+
+pub fn tank_system(mut commands:Commands, tank:Query<(&mut Tank, &RigidBodyHandleComponent, Entity)>, mut rigid_body_set:ResMut<RigidBodySet>, time:Res<Time>, mut sprites:Query<&mut TextureAtlasSprite>, tilemap:Query<&Tilemap>) {
     tank.for_each_mut(|(mut tank, rigid_body, e)| {
         if let Some(rigid_body) = rigid_body_set.get_mut(rigid_body.handle()) {
+            let model = tank.model;
             let dir:Vec2 = [rigid_body.position().rotation.re, rigid_body.position().rotation.im].into();
+            let side = dir.perp();
+            let p:Vec2 = [rigid_body.position().translation.x, rigid_body.position().translation.y].into();
+            let v:Vec2 = [rigid_body.linvel().x, rigid_body.linvel().y].into();
+            let terrain = terrain_modifiers_at(&tilemap, p);
+            let forward_speed = v.dot(dir);
+
+            // tracks[0] is the right track and tracks[1] the left track
+            let positions = [p - side * model.track_width / 2.0, p + side * model.track_width / 2.0];
+            for i in 0..2 {
+                let throttle = tank.tracks[i].max(-1.0).min(1.0);
+                if throttle == 0.0 {
+                    continue;
+                }
+
+                // the engine runs out of power as the track approaches max speed
+                let speed = forward_speed * throttle.signum();
+                let power = (1.0 - speed / model.max_speed).max(0.0).min(1.0);
+                let force = dir * throttle * model.engine_power * power * terrain.force;
+                rigid_body.apply_force_at_point([force.x, force.y].into(), [positions[i].x, positions[i].y].into(), true);
+            }
+
+            // tracks moving in opposite directions help turning, while gripping tracks resist it
             let diff = tank.tracks[0] - tank.tracks[1];
-            let force = (dir * tank.tracks[0] + dir * tank.tracks[1]) * time.delta_seconds() * 400.0;
-            tank.tracks_distance = tank.tracks * time.delta_seconds() + tank.tracks_distance;
-            rigid_body.set_angvel(diff, true);
-            rigid_body.apply_force([force.x, force.y].into(), true);
+            let torque = diff * model.turn_torque * terrain.turn - rigid_body.angvel() * (1.0 - model.track_slip) * TRACK_GRIP;
+            rigid_body.apply_torque(torque, true);
 
+            tank.tracks_distance = tank.tracks * time.delta_seconds() + tank.tracks_distance;
             if tank.tracks_distance.length() > 0.2 {
                 tank.tracks_distance = [0.0, 0.0].into();
                 if let Ok(mut sprite) = sprites.get_component_mut::<TextureAtlasSprite>(e) {
                     sprite.flip_x = !sprite.flip_x;
                 }
+
+                if terrain.track_marks {
+                    commands.spawn().insert(ThingBuilder {
+                        translation:p.extend(0.0),
+                        rotation:Quat::from_rotation_z(rigid_body.position().rotation.angle()),
+                        thing_type:ThingType::Effect(EffectType::TrackMark),
+                        ..Default::default()
+                    });
+                }
             }
         }
     });
-}
\ No newline at end of file
+}
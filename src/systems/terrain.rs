@@ -0,0 +1,34 @@
+use bevy::prelude::*;
+
+use crate::{Terrain, Tilemap};
+
+// multipliers applied to tank handling depending on the terrain below the tank
+#[derive(Debug, Clone, Copy)]
+pub struct TerrainModifiers {
+    pub drag_front:f32,
+    pub drag_side:f32,
+    pub force:f32,
+    pub turn:f32,
+    pub track_marks:bool
+}
+
+impl From<Terrain> for TerrainModifiers {
+    fn from(terrain: Terrain) -> Self {
+        match terrain {
+            Terrain::Normal => Self { drag_front:1.0, drag_side:1.0, force:1.0, turn:1.0, track_marks:false },
+            Terrain::Mud => Self { drag_front:2.0, drag_side:1.5, force:0.6, turn:0.7, track_marks:true },
+            Terrain::Ice => Self { drag_front:0.2, drag_side:0.15, force:0.5, turn:0.6, track_marks:false },
+            Terrain::Road => Self { drag_front:0.8, drag_side:1.0, force:1.3, turn:1.0, track_marks:false },
+            Terrain::Water => Self { drag_front:3.0, drag_side:2.0, force:0.4, turn:0.5, track_marks:false },
+        }
+    }
+}
+
+pub fn terrain_modifiers_at(tilemap:&Query<&Tilemap>, p:Vec2) -> TerrainModifiers {
+    let terrain = match tilemap.single() {
+        Ok(tilemap) => tilemap.get_tile_at(p).map(|t| t.terrain).unwrap_or_default(),
+        Err(_) => Terrain::default()
+    };
+
+    terrain.into()
+}
@@ -1,54 +1,120 @@
+use std::f32::consts::PI;
 
 use bevy::prelude::*;
-use rand::random;
 
-use crate::{PlayAudioEvent, ThingBuilder, Turret};
+use crate::{EffectType, NoiseEvent, PlayAudioEvent, ThingBuilder, ThingType, Turret, TurretFireEvent, Weapon};
 
+// wraps an angle into -PI..PI
+fn wrap_angle(a:f32) -> f32 {
+    let mut a = a % (2.0 * PI);
+    if a > PI {
+        a -= 2.0 * PI;
+    } else if a < -PI {
+        a += 2.0 * PI;
+    }
+    a
+}
 
-pub fn turret_system(mut play_audio:EventWriter<PlayAudioEvent>, mut commands:Commands, turrets:Query<(Entity, &mut Turret, &Parent)>, mut transforms:Query<(&mut Transform,)>, time:Res<Time>) {
-    turrets.for_each_mut(|(turret_entity, turret, parent_entity), | {
+// clamps an angle, measured from the start of an arc, to the arc, snapping to the nearer end if outside
+fn clamp_to_arc(offset:f32, arc:f32) -> f32 {
+    if offset <= arc {
+        offset
+    } else if offset - arc < 2.0 * PI - offset {
+        arc
+    } else {
+        0.0
+    }
+}
+
+// predicts where to aim such that a projectile fired from shooter hits a target moving at a constant velocity
+pub fn lead_target(shooter:Vec3, target:Vec3, target_velocity:Vec3, projectile_speed:f32) -> Vec3 {
+    let d = target - shooter;
+    let a = target_velocity.dot(target_velocity) - projectile_speed * projectile_speed;
+    let b = 2.0 * d.dot(target_velocity);
+    let c = d.dot(d);
+
+    // solve a*t^2 + b*t + c = 0 for the smallest positive time of impact
+    let t = if a.abs() < 0.0001 {
+        if b.abs() < 0.0001 { 0.0 } else { -c / b }
+    } else {
+        let disc = b * b - 4.0 * a * c;
+        if disc < 0.0 {
+            return target;
+        }
+        let sqrt = disc.sqrt();
+        let t1 = (-b - sqrt) / (2.0 * a);
+        let t2 = (-b + sqrt) / (2.0 * a);
+        if t1 > 0.0 && t2 > 0.0 { t1.min(t2) } else { t1.max(t2) }
+    };
+
+    if t <= 0.0 {
+        return target;
+    }
+
+    target + target_velocity * t
+}
+
+pub fn turret_system(mut play_audio:EventWriter<PlayAudioEvent>, mut noise:EventWriter<NoiseEvent>, mut fired:EventWriter<TurretFireEvent>, mut commands:Commands, turrets:Query<(Entity, &mut Turret, &Parent)>, mut transforms:Query<(&mut Transform,)>, time:Res<Time>) {
+    turrets.for_each_mut(|(turret_entity, mut turret, parent_entity), | {
         let mut parent_translation = Vec3::default();
         let mut parent_rotation = Quat::default();
         if let Ok(parent_transform) = transforms.get_component::<Transform>(parent_entity.0) {
             parent_translation = parent_transform.translation;
             parent_rotation = parent_transform.rotation;
         }
+
+        turret.cooldown = (turret.cooldown - time.delta_seconds()).max(0.0);
+
         if let Ok(mut turret_transform) = transforms.get_component_mut::<Transform>(turret_entity) {
-            let target = turret.target;
-            let pos = parent_translation;
-            let v = target - pos;
-
-            if v.length() > 0.0 {
-                let b = v.normalize();
-                let a = Vec3::new(1.0, 0.0, 0.0);
-                let rot_global = Quat::from_rotation_arc(a, b);
-                let a = parent_rotation.mul_vec3(a);
-                let rot_relative = Quat::from_rotation_arc(a, b);
-
-                turret_transform.rotation = rot_relative;
-
-                let mut turret = turret;
-                turret.cooldown -= time.delta_seconds();
-                if turret.cooldown <= 0.0 {
-                    turret.cooldown = 0.0;
-                }
-
-                if turret.cooldown == 0.0 && turret.trigger {
-                    turret.cooldown = 1.0;
-                    let mut e = commands.spawn();
-                    let v = Vec3::new(1.0, 0.0, 0.0) * 0.5;
-                    let v =  rot_global * v;
-                    play_audio.send(format!("sfx/shoot_{}.ogg", 1 + random::<u8>() % 3).into());
-                    e.insert(ThingBuilder {
-                        translation:parent_translation + v,
-                        rotation:rot_global,
-                        thing_type:crate::ThingType::Bullet,
-                        owner:Some(parent_entity.0)
-                    });
-
-                }
+            let v = parent_rotation.inverse() * (turret.target - parent_translation);
+            if v.truncate().length() > 0.0 {
+                // rotate towards the target, never through the range outside the traverse limits
+                let wanted = v.y.atan2(v.x);
+                let max_step = turret.max_angular_speed * time.delta_seconds();
+                let diff = match turret.traverse {
+                    Some((min, max)) => {
+                        // measured from min the arc is continuous, also when it crosses -PI..PI
+                        let arc = (max - min).rem_euclid(2.0 * PI);
+                        let current = clamp_to_arc((turret.angle - min).rem_euclid(2.0 * PI), arc);
+                        // a turret outside its arc, such as when the limits were set after spawning, snaps into it
+                        turret.angle = wrap_angle(min + current);
+                        clamp_to_arc((wanted - min).rem_euclid(2.0 * PI), arc) - current
+                    },
+                    None => wrap_angle(wanted - turret.angle)
+                };
+                turret.angle = wrap_angle(turret.angle + diff.max(-max_step).min(max_step));
+                turret.aligned = wrap_angle(wanted - turret.angle).abs() <= turret.aim_tolerance;
+            } else {
+                turret.aligned = false;
+            }
+
+            turret_transform.rotation = Quat::from_rotation_z(turret.angle);
+
+            if let (Some(weapon), true) = (turret.weapon, turret.cooldown == 0.0 && turret.trigger && turret.aligned) {
+                turret.cooldown = turret.reload_time;
+                let projectile = match weapon {
+                    Weapon::Cannon => ThingType::Bullet
+                };
+                let rot_global = parent_rotation * Quat::from_rotation_z(turret.angle);
+                let muzzle = parent_translation + rot_global * Vec3::new(turret.muzzle_offset, 0.0, 0.0);
+                play_audio.send(PlayAudioEvent::new("tank.fire").with_position(muzzle));
+                noise.send(NoiseEvent::gunshot(parent_translation, parent_entity.0));
+                fired.send(TurretFireEvent { tank:parent_entity.0, position:muzzle });
+                commands.spawn().insert(ThingBuilder {
+                    translation:muzzle,
+                    rotation:rot_global,
+                    thing_type:projectile,
+                    owner:Some(parent_entity.0),
+                    ..Default::default()
+                });
+                commands.spawn().insert(ThingBuilder {
+                    translation:muzzle,
+                    rotation:rot_global,
+                    thing_type:ThingType::Effect(EffectType::MuzzleFlash),
+                    ..Default::default()
+                });
             }
         }
     });
 
-}
\ No newline at end of file
+}
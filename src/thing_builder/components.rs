@@ -1,9 +1,15 @@
 use bevy::{math::{Quat, Vec3}, prelude::Entity};
 
+use crate::TankClass;
+
+pub const BULLET_SPEED:f32 = 10.0;
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum EffectType {
     BulletHit,
-    Explosion
+    Explosion,
+    TrackMark,
+    MuzzleFlash
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -25,5 +31,8 @@ pub struct ThingBuilder {
     pub translation:Vec3,
     pub rotation:Quat,
     pub thing_type:ThingType,
-    pub owner:Option<Entity>
+    pub owner:Option<Entity>,
+    pub tank_class:TankClass,
+    // traverse limits of the turret relative to the hull, in radians
+    pub turret_traverse:Option<(f32, f32)>
 }
\ No newline at end of file
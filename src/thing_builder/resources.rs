@@ -1,6 +1,6 @@
 use bevy::{prelude::Handle, sprite::TextureAtlas};
 
-use crate::ThingType;
+use crate::{EffectType, ThingType};
 
 #[derive(Default)]
 pub struct TextureAtlases {
@@ -15,6 +15,7 @@ impl TextureAtlases {
             ThingType::Unknown => self.tanks.clone(),
             ThingType::Tank => self.tanks.clone(),
             ThingType::Bullet => self.tanks.clone(),
+            ThingType::Effect(EffectType::TrackMark) => self.tanks.clone(),
             ThingType::Effect(_) => self.explosion.clone()
         }
     }
@@ -24,6 +25,7 @@ impl TextureAtlases {
             ThingType::Unknown => {0}
             ThingType::Tank => {0}
             ThingType::Bullet => {2},
+            ThingType::Effect(EffectType::TrackMark) => {1},
             ThingType::Effect(_) => {0}
         }
     }
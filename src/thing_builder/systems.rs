@@ -26,11 +26,13 @@ pub fn thing_builder_added_system(mut commands:Commands, query:Query<(Entity, &T
         match tb.thing_type {
             ThingType::Unknown => {}
             ThingType::Tank => {
+                let model = tb.tank_class.track_model();
                 let a = tb.rotation.to_axis_angle().1;
                 let rigid_body = RigidBodyBuilder::new_dynamic()
                 .translation(x, y)
                 .rotation(a);
                 let collider = ColliderBuilder::cuboid(0.5, 0.5)
+                .density(model.density)
                 .user_data(e.id().to_bits() as u128);
                 e.insert(rigid_body);
                 e.insert(collider);
@@ -63,7 +65,7 @@ pub fn thing_builder_added_system(mut commands:Commands, query:Query<(Entity, &T
                     },
                     ..Default::default()
                 })
-                .insert(Turret::default())
+                .insert(Turret::default().with_max_angular_speed(tb.tank_class.turret_speed()).with_traverse(tb.turret_traverse))
                 .insert(GamePiece::default())
                 .id();
 
@@ -71,7 +73,7 @@ pub fn thing_builder_added_system(mut commands:Commands, query:Query<(Entity, &T
                 // is not properly propagated: https://github.com/bevyengine/bevy/issues/1807
                 // can be fixed by doing this instead
                 commands.entity(tank).push_children(&[turret]);
-                commands.entity(tank).insert(Tank::new(turret));
+                commands.entity(tank).insert(Tank::new(turret).with_model(model));
 
                 let tracks = commands.spawn_bundle(SpriteSheetBundle {
                     texture_atlas:texture_atlases.tanks.clone(),
@@ -91,8 +93,7 @@ pub fn thing_builder_added_system(mut commands:Commands, query:Query<(Entity, &T
                 commands.entity(tank).push_children(&[tracks]);
             }
             ThingType::Bullet => {
-                let speed = 10.0;
-                let v = Vec3::new(speed, 0.0, 0.0);
+                let v = Vec3::new(BULLET_SPEED, 0.0, 0.0);
                 let v = tb.rotation * v;
                 let a = tb.rotation.to_axis_angle().1;
                 let rigid_body = RigidBodyBuilder::new_dynamic()
@@ -160,6 +161,42 @@ pub fn thing_builder_added_system(mut commands:Commands, query:Query<(Entity, &T
                         e.insert_bundle(sprite_sheet_bundle);
 
                     },
+                    EffectType::TrackMark => {
+                        // below tanks but above the tilemap
+                        transform.translation.z = 0.1;
+                        e.insert(Effect::new(10.0, 0.0, true).with_start_fade(0.5));
+
+                        let sprite_sheet_bundle = SpriteSheetBundle {
+                            texture_atlas:texture_atlases.get_atlas(tb.thing_type),
+                            transform,
+                            sprite:TextureAtlasSprite {
+                                index:texture_atlases.get_index(tb.thing_type),
+                                color:Color::rgb(0.25, 0.2, 0.15),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        };
+
+                        e.insert_bundle(sprite_sheet_bundle);
+                    }
+                    EffectType::MuzzleFlash => {
+                        transform.scale = transform.scale * 0.35;
+                        transform.translation.z = 1.2;
+                        e.insert(Effect::new(0.08, 1.5, true));
+
+                        let sprite_sheet_bundle = SpriteSheetBundle {
+                            texture_atlas:texture_atlases.get_atlas(tb.thing_type),
+                            transform,
+                            sprite:TextureAtlasSprite {
+                                index:texture_atlases.get_index(tb.thing_type),
+                                color:Color::rgb(1.0, 0.9, 0.5),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        };
+
+                        e.insert_bundle(sprite_sheet_bundle);
+                    }
                 }
 
                 
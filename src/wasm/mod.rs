@@ -1,9 +1,9 @@
-use bevy::{input::InputSystem, prelude::*};
+use bevy::{input::InputSystem, prelude::*, utils::HashMap};
 use bevy::window::WindowResized;
 use wasm_bindgen::prelude::*;
-use web_sys::{TouchEvent, TouchList};
+use web_sys::{Touch, TouchEvent, TouchList};
 
-use crate::mouse::{Mouse};
+use crate::{GameCamera, TouchControls, TouchRole, mouse::{Mouse}};
 
 
 #[wasm_bindgen]
@@ -59,28 +59,87 @@ fn resizer(mut windows:ResMut<Windows>, mut window_resized_events: EventWriter<W
     }
 }
 
-fn pool_touch_system(mut mouse:ResMut<Mouse>, windows:Res<Windows>, mut mouse_button_input: ResMut<Input<MouseButton>>,) {
+// role of every active touch, by identifier, and the last pinch distance
+#[derive(Default)]
+struct TouchTracker {
+    roles:HashMap<i32, TouchRole>,
+    pinch:Option<f32>
+}
+
+fn touch_position(touch:&Touch, window:&Window) -> Vec2 {
+    Vec2::new(touch.client_x() as f32, window.height() as f32 - touch.client_y() as f32)
+}
+
+fn pool_touch_system(mut mouse:ResMut<Mouse>,
+    windows:Res<Windows>,
+    mut mouse_button_input:ResMut<Input<MouseButton>>,
+    mut touch_controls:ResMut<TouchControls>,
+    mut tracker:ResMut<TouchTracker>,
+    mut camera:Query<&mut GameCamera>) {
     if let Some(window) = windows.get_primary() {
+        touch_controls.enabled = true;
         while let Some(touch_event) = pop_touch_event() {
             let t = touch_event.type_();
-            let touches:TouchList = touch_event.touches();
+            let changed:TouchList = touch_event.changed_touches();
+
+            for i in 0..changed.length() {
+                if let Some(touch) = changed.get(i) {
+                    let id = touch.identifier();
+                    let p = touch_position(&touch, window);
+                    if t == "touchstart" {
+                        let role = TouchControls::role_at(p, window.width(), window.height());
+                        match role {
+                            TouchRole::Joystick => touch_controls.begin_joystick(p),
+                            TouchRole::Fire => touch_controls.fire = true,
+                            TouchRole::Pointer => {}
+                        }
+                        tracker.roles.insert(id, role);
+                    } else if t == "touchmove" {
+                        if tracker.roles.get(&id) == Some(&TouchRole::Joystick) {
+                            touch_controls.move_joystick(p);
+                        }
+                    } else if t == "touchend" || t == "touchcancel" {
+                        match tracker.roles.remove(&id) {
+                            Some(TouchRole::Joystick) => touch_controls.end_joystick(),
+                            Some(TouchRole::Fire) => touch_controls.fire = false,
+                            _ => {}
+                        }
+                    }
+                }
+            }
 
+            // the remaining touches act as the mouse, two of them pinch to zoom
+            let touches:TouchList = touch_event.touches();
+            let mut pointers = Vec::new();
             for i in 0..touches.length() {
                 if let Some(touch) = touches.get(i) {
-                    let _id = touch.identifier();
-                    let x = touch.client_x() as f32;
-                    let y = window.height() as f32 - touch.client_y() as f32;
-                    mouse.pos_screen = Vec2::new(x, y);
-                    mouse.has_touch = true;
+                    if tracker.roles.get(&touch.identifier()) == Some(&TouchRole::Pointer) {
+                        pointers.push(touch_position(&touch, window));
+                    }
                 }
             }
 
-            if t == "touchstart" {
+            if pointers.len() == 1 {
+                tracker.pinch = None;
+                mouse.pos_screen = pointers[0];
+                mouse.has_touch = true;
                 mouse_button_input.press(MouseButton::Left);
-            } else if t ==  "touchend" {
-                mouse_button_input.release(MouseButton::Left);
-            } else if t ==  "touchmove" {
-               
+            } else {
+                if mouse_button_input.pressed(MouseButton::Left) {
+                    mouse_button_input.release(MouseButton::Left);
+                }
+
+                if pointers.len() == 2 {
+                    let d = pointers[0].distance(pointers[1]);
+                    if let (Some(last), Ok(mut camera)) = (tracker.pinch, camera.single_mut()) {
+                        if last > 0.0 {
+                            camera.zoom_by(d / last);
+                        }
+                    }
+                    tracker.pinch = Some(d);
+                } else {
+                    tracker.pinch = None;
+                }
             }
         }
     }
@@ -96,7 +155,8 @@ impl Plugin for WASMPlugin {
         app.add_system(resizer.system());
 
         if has_touch() {
-            app.add_system_to_stage(CoreStage::PreUpdate, pool_touch_system.system().after(InputSystem));
+            app.insert_resource(TouchTracker::default());
+            app.add_system_to_stage(CoreStage::PreUpdate, pool_touch_system.system().after(InputSystem).before(crate::ActionSystem));
         }
     }
 }
\ No newline at end of file